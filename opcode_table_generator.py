@@ -261,23 +261,23 @@ OPCODE_TABLE = """
 
 def main():
     operations = set()
-    address_func = {
-        "imm" : "immediate",
-        "zp" : "zero_page",
-        "zpx" : "zero_page_x",
-        "zpy" : "zero_page_y",
-        "izx" : "indirect_x",
-        "izy" : "indirect_y",
-        "abs" : "absolute",
-        "abx" : "absolute_x",
-        "aby": "absolute_y",
-        "ind": "indirect",
-        "rel": "relative",
-        "imp": "implied"
+    addressing_modes = {
+        "imm" : "Immediate",
+        "zp" : "ZeroPage",
+        "zpx" : "ZeroPageX",
+        "zpy" : "ZeroPageY",
+        "izx" : "IndirectX",
+        "izy" : "IndirectY",
+        "abs" : "Absolute",
+        "abx" : "AbsoluteX",
+        "aby": "AbsoluteY",
+        "ind": "Indirect",
+        "rel": "Relative",
+        "imp": "Implied"
     }
 
     print("// Autogenerated from opcode_table_generator.py")
-    print("static OPCODE_TABLE: [OpCode; 256] = [")
+    print("pub(crate) static OPCODE_TABLE: [OpCode; 256] = [")
     for line in OPCODE_TABLE.split('\n'):
         line = line.strip()
         if line:
@@ -290,21 +290,36 @@ def main():
                 addr = 'imp'
             elif len(opcode_info) == 4:
                 opcode,name,addr,cycles = opcode_info
+            elif len(opcode_info) == 2:
+                # KIL doesn't list cycles: it jams the CPU after fetching
+                # the opcode and its next byte
+                opcode,name = opcode_info
+                addr = 'imp'
+                cycles = '2'
+            unofficial = name.startswith('*')
             name = name.replace('*', '')
             addr = addr.replace('*', '')
+            # Branches account for their own extra cycles
+            page_cross_penalty = cycles.endswith('*') and addr != 'rel'
             cycles = cycles.replace('*', '')
-            addressing = address_func[addr]
+            addressing = addressing_modes[addr]
             operations.add(name.lower())
             print('// Opcode: 0x%s' % opcode)
-            print('OpCode { execute: CPU::%s, addressing: CPU::%s, name: "%s", addr_name: "%s", cycles: %s },' % (name.lower(), addressing, name, addr.upper(), cycles))
+            print('OpCode { operation: Operation::%s, name: "%s", addressing: AddressingMode::%s, cycles: %s, page_cross_penalty: %s, unofficial: %s },' % (name.capitalize(), name, addressing, cycles, str(page_cross_penalty).lower(), str(unofficial).lower()))
     print("];")
 
-    print("impl CPU {")
+    print("#[derive(Debug, Copy, Clone, PartialEq, Eq)]")
+    print("pub(crate) enum Operation {")
     for op_name in sorted(operations):
-        print("fn %s(&mut self, _address: Address) {" % op_name)
-        print('  todo!("%s Not Implemented")' % op_name)
-        print('}')
-        print()
+        print("    %s," % op_name.capitalize())
     print("}")
+    print()
+
+    # Dispatch for CPU::execute
+    print("match operation {")
+    for op_name in sorted(operations):
+        print("    Operation::%s => self.%s(address)," % (op_name.capitalize(), op_name))
+    print("}")
+
 if __name__ == '__main__':
     main()
\ No newline at end of file
@@ -0,0 +1,1473 @@
+use bitflags::bitflags;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{audio::AudioBuffer, blip::BlipBuffer};
+
+use self::vrc6::Vrc6;
+
+mod vrc6;
+
+bitflags! {
+    /// A set of APU channels, for muting and soloing.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Channels: u8 {
+        const PULSE1 = 1;
+        const PULSE2 = 1 << 1;
+        const TRIANGLE = 1 << 2;
+        const NOISE = 1 << 3;
+        const DMC = 1 << 4;
+        /// Cartridge expansion audio
+        const EXPANSION = 1 << 5;
+    }
+}
+
+/// The 2A03's audio processing unit, clocked once per CPU cycle.
+pub struct Apu {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    vrc6: Option<Vrc6>,
+    frame_counter: FrameCounter,
+    cycles: u64,
+    muted: Channels,
+    solo: Channels,
+    blip: Option<BlipBuffer>,
+    filters: Option<OutputFilters>,
+    filters_enabled: bool,
+    samples: AudioBuffer,
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        Self {
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            triangle: Triangle::default(),
+            noise: Noise::new(),
+            dmc: Dmc::new(),
+            vrc6: None,
+            frame_counter: FrameCounter::default(),
+            cycles: 0,
+            muted: Channels::empty(),
+            solo: Channels::empty(),
+            blip: None,
+            filters: None,
+            filters_enabled: true,
+            samples: AudioBuffer::new(0),
+        }
+    }
+
+    /// Sets the rate at which `clock` collects output samples, buffering up
+    /// to a second of them. A rate of 0, the default, collects none.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.blip = (sample_rate > 0).then(|| BlipBuffer::new(CPU_CLOCK, sample_rate));
+        self.filters = (sample_rate > 0).then(|| OutputFilters::new(sample_rate));
+        self.samples = AudioBuffer::new(sample_rate as usize);
+    }
+
+    /// Nudges the sample rate without clearing what is buffered, see
+    /// `audio::RateControl`.
+    pub fn adjust_sample_rate(&mut self, sample_rate: u32) {
+        if let Some(blip) = &mut self.blip {
+            blip.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Runs the samples through the console's own high-pass and low-pass
+    /// filters, on by default. Without them the mix is only recentered
+    /// around 0.
+    pub fn set_filters_enabled(&mut self, enabled: bool) {
+        self.filters_enabled = enabled;
+    }
+
+    /// The collected output samples, between -1.0 and 1.0.
+    pub fn samples_mut(&mut self) -> &mut AudioBuffer {
+        &mut self.samples
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x4000..=0x4003 => self.pulse1.write(address & 0x03, value),
+            0x4004..=0x4007 => self.pulse2.write(address & 0x03, value),
+            0x4008..=0x400B => self.triangle.write(address & 0x03, value),
+            0x400C..=0x400F => self.noise.write(address & 0x03, value),
+            0x4010..=0x4013 => self.dmc.write(address & 0x03, value),
+            STATUS => {
+                self.pulse1.length.set_enabled(value & 0x01 != 0);
+                self.pulse2.length.set_enabled(value & 0x02 != 0);
+                self.triangle.length.set_enabled(value & 0x04 != 0);
+                self.noise.length.set_enabled(value & 0x08 != 0);
+                self.dmc.set_enabled(value & 0x10 != 0);
+            }
+            FRAME_COUNTER => self.frame_counter.write(value, self.cycles),
+            _ => {}
+        }
+    }
+
+    /// Handles the console's reset button: every channel is silenced as if
+    /// $4015 was written with 0, and the frame counter restarts in the
+    /// mode it was in.
+    pub fn reset(&mut self) {
+        self.write(STATUS, 0x00);
+        self.frame_counter.reset(self.cycles);
+        self.dmc.reset();
+    }
+
+    /// Adds the VRC6's channels to the mix, for boards that carry it.
+    pub fn set_vrc6_enabled(&mut self, enabled: bool) {
+        self.vrc6 = enabled.then(Vrc6::default);
+    }
+
+    /// Passes a write to cartridge space on to the expansion audio, if any.
+    pub(crate) fn write_expansion(&mut self, address: u16, value: u8) {
+        if let Some(vrc6) = &mut self.vrc6 {
+            vrc6.write(address, value);
+        }
+    }
+
+    pub fn clock(&mut self) {
+        let frame = self.frame_counter.clock();
+        if frame.quarter {
+            self.pulse1.envelope.clock(self.pulse1.length.halt);
+            self.pulse2.envelope.clock(self.pulse2.length.halt);
+            self.triangle.clock_linear_counter();
+            self.noise.envelope.clock(self.noise.length.halt);
+        }
+        if frame.half {
+            self.pulse1.length.clock();
+            self.pulse2.length.clock();
+            self.triangle.length.clock();
+            self.noise.length.clock();
+            self.pulse1.clock_sweep();
+            self.pulse2.clock_sweep();
+        }
+
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        if let Some(vrc6) = &mut self.vrc6 {
+            vrc6.clock();
+        }
+
+        // The pulse timers run at half the CPU clock
+        if self.cycles & 1 == 1 {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+
+        if self.blip.is_some() {
+            let output = self.output();
+            if let Some(sample) = self.blip.as_mut().and_then(|blip| blip.clock(output)) {
+                let sample = match &mut self.filters {
+                    Some(filters) if self.filters_enabled => filters.process(sample),
+                    _ => sample * 2.0 - 1.0,
+                };
+                self.samples.push(sample);
+            }
+        }
+
+        self.cycles += 1;
+    }
+
+    pub fn snapshot(&self) -> ApuState {
+        ApuState {
+            pulse1: self.pulse1.clone(),
+            pulse2: self.pulse2.clone(),
+            triangle: self.triangle.clone(),
+            noise: self.noise.clone(),
+            dmc: self.dmc.clone(),
+            vrc6: self.vrc6.clone(),
+            frame_counter: self.frame_counter.clone(),
+            cycles: self.cycles,
+        }
+    }
+
+    /// Restores a snapshot taken with `snapshot`. The sample rate, buffered
+    /// samples and mixer settings are left untouched.
+    pub fn restore(&mut self, state: &ApuState) {
+        self.pulse1 = state.pulse1.clone();
+        self.pulse2 = state.pulse2.clone();
+        self.triangle = state.triangle.clone();
+        self.noise = state.noise.clone();
+        self.dmc = state.dmc.clone();
+        self.vrc6 = state.vrc6.clone();
+        self.frame_counter = state.frame_counter.clone();
+        self.cycles = state.cycles;
+    }
+
+    pub fn muted(&self) -> Channels {
+        self.muted
+    }
+
+    /// Leaves `channels` out of the mix. They keep running, so unmuting
+    /// them picks up where the game is.
+    pub fn set_muted(&mut self, channels: Channels) {
+        self.muted = channels;
+    }
+
+    pub fn solo(&self) -> Channels {
+        self.solo
+    }
+
+    /// Mixes only `channels`, unless empty. Muted channels stay silent.
+    pub fn set_solo(&mut self, channels: Channels) {
+        self.solo = channels;
+    }
+
+    fn audible(&self) -> Channels {
+        let playing = if self.solo.is_empty() {
+            Channels::all()
+        } else {
+            self.solo
+        };
+        playing - self.muted
+    }
+
+    /// The mixed output of the channels, between 0.0 and 1.0.
+    pub fn output(&self) -> f32 {
+        let audible = self.audible();
+        let level = |channel, output: u8| {
+            if audible.contains(channel) {
+                f32::from(output)
+            } else {
+                0.0
+            }
+        };
+
+        let pulse = level(Channels::PULSE1, self.pulse1.output())
+            + level(Channels::PULSE2, self.pulse2.output());
+        let pulse = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+
+        let tnd = level(Channels::TRIANGLE, self.triangle.output()) / 8227.0
+            + level(Channels::NOISE, self.noise.output()) / 12241.0
+            + level(Channels::DMC, self.dmc.output_level) / 22638.0;
+        let tnd = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+
+        // Expansion audio is mixed linearly, at about the level of the
+        // APU's pulses
+        let expansion = self.vrc6.as_ref().map_or(0.0, |vrc6| {
+            level(Channels::EXPANSION, vrc6.output()) * 0.00752
+        });
+
+        pulse + tnd + expansion
+    }
+
+    /// Reads $4015 without the side effects of `read_status`. Bit 5 is not
+    /// driven by the APU and left clear.
+    pub fn peek_status(&self) -> u8 {
+        u8::from(self.pulse1.length.active())
+            | u8::from(self.pulse2.length.active()) << 1
+            | u8::from(self.triangle.length.active()) << 2
+            | u8::from(self.noise.length.active()) << 3
+            | u8::from(self.dmc.bytes_remaining > 0) << 4
+            | u8::from(self.frame_counter.irq) << 6
+            | u8::from(self.dmc.irq) << 7
+    }
+
+    /// Reads $4015: which channels are still playing and the pending
+    /// interrupts. Acknowledges the frame interrupt.
+    pub fn read_status(&mut self) -> u8 {
+        let status = self.peek_status();
+        self.frame_counter.irq = false;
+        status
+    }
+
+    /// The address the DMC wants its next sample byte from, if its buffer
+    /// is empty.
+    pub(crate) fn dmc_read_address(&self) -> Option<u16> {
+        self.dmc.read_address()
+    }
+
+    /// Hands the DMC the byte it asked for with `dmc_read_address`.
+    pub(crate) fn dmc_fill(&mut self, value: u8) {
+        self.dmc.fill(value);
+    }
+
+    /// Whether the frame counter or the DMC is holding the IRQ line.
+    pub fn irq(&self) -> bool {
+        self.frame_counter.irq || self.dmc.irq
+    }
+
+    /// Whether the frame counter is requesting an interrupt.
+    pub fn frame_irq(&self) -> bool {
+        self.frame_counter.irq
+    }
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Everything that makes up the APU's sound: timers, sequencers,
+/// envelopes, the frame counter's phase and the DMC's fetch state.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApuState {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    vrc6: Option<Vrc6>,
+    frame_counter: FrameCounter,
+    cycles: u64,
+}
+
+/// The NTSC CPU clock rate in Hz.
+pub const CPU_CLOCK: u32 = 1_789_773;
+
+pub(crate) const STATUS: u16 = 0x4015;
+pub(crate) const FRAME_COUNTER: u16 = 0x4017;
+
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Pulse {
+    duty: u8,
+    // Its halt flag also loops the envelope
+    length: LengthCounter,
+    envelope: Envelope,
+    sweep_enabled: bool,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_divider: u8,
+    sweep_reload: bool,
+    // Pulse 1 negates with one's complement, so it sweeps one lower
+    ones_complement: bool,
+    timer_period: u16,
+    timer: u16,
+    sequence_step: u8,
+}
+
+impl Pulse {
+    fn new(ones_complement: bool) -> Self {
+        Self {
+            duty: 0,
+            length: LengthCounter::default(),
+            envelope: Envelope::default(),
+            sweep_enabled: false,
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_divider: 0,
+            sweep_reload: false,
+            ones_complement,
+            timer_period: 0,
+            timer: 0,
+            sequence_step: 0,
+        }
+    }
+
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.duty = value >> 6;
+                self.length.halt = value & 0x20 != 0;
+                self.envelope.write(value);
+            }
+            1 => {
+                self.sweep_enabled = value & 0x80 != 0;
+                self.sweep_period = (value >> 4) & 0x07;
+                self.sweep_negate = value & 0x08 != 0;
+                self.sweep_shift = value & 0x07;
+                self.sweep_reload = true;
+            }
+            2 => self.timer_period = (self.timer_period & 0x0700) | u16::from(value),
+            _ => {
+                self.timer_period = (self.timer_period & 0x00ff) | (u16::from(value & 0x07) << 8);
+                self.length.load(value >> 3);
+                self.sequence_step = 0;
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            self.sequence_step = (self.sequence_step + 1) & 0x07;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn sweep_target(&self) -> u16 {
+        let change = self.timer_period >> self.sweep_shift;
+        if self.sweep_negate {
+            let change = change + u16::from(self.ones_complement);
+            self.timer_period.saturating_sub(change)
+        } else {
+            self.timer_period + change
+        }
+    }
+
+    // The sweep unit silences the channel whenever the current or target
+    // period is out of range, even while it is disabled
+    fn sweep_muted(&self) -> bool {
+        self.timer_period < 8 || self.sweep_target() > 0x7ff
+    }
+
+    fn clock_sweep(&mut self) {
+        if self.sweep_divider == 0
+            && self.sweep_enabled
+            && self.sweep_shift > 0
+            && !self.sweep_muted()
+        {
+            self.timer_period = self.sweep_target();
+        }
+
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = self.sweep_period;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        let duty = DUTY_CYCLES[usize::from(self.duty)][usize::from(self.sequence_step)];
+        if !self.length.active() || duty == 0 || self.sweep_muted() {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
+
+// Volume control shared by the pulse and noise channels: either a
+// constant volume, or a decay from 15 to 0 clocked by quarter frames.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Envelope {
+    start: bool,
+    constant_volume: bool,
+    // The constant volume, or the decay's period
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    fn write(&mut self, value: u8) {
+        self.constant_volume = value & 0x10 != 0;
+        self.volume = value & 0x0f;
+    }
+
+    fn clock(&mut self, looping: bool) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn volume(&self) -> u8 {
+        if self.constant_volume {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
+
+// Note durations in half frames, indexed by the top 5 bits of a channel's
+// last register
+#[rustfmt::skip]
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14,
+    12, 16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+// Silences a channel once its note has played for the loaded number of
+// half frames, unless halted. Disabling the channel through $4015 clears
+// it and keeps it from loading.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct LengthCounter {
+    enabled: bool,
+    halt: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    fn load(&mut self, index: u8) {
+        if self.enabled {
+            self.counter = LENGTH_TABLE[usize::from(index)];
+        }
+    }
+
+    fn clock(&mut self) {
+        if !self.halt && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+
+    fn active(&self) -> bool {
+        self.counter > 0
+    }
+}
+
+#[rustfmt::skip]
+const TRIANGLE_SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0,
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Triangle {
+    // Its halt flag is also the linear counter's control flag
+    length: LengthCounter,
+    linear_counter_load: u8,
+    linear_counter: u8,
+    linear_counter_reload: bool,
+    timer_period: u16,
+    timer: u16,
+    sequence_step: u8,
+}
+
+impl Triangle {
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.length.halt = value & 0x80 != 0;
+                self.linear_counter_load = value & 0x7f;
+            }
+            1 => {}
+            2 => self.timer_period = (self.timer_period & 0x0700) | u16::from(value),
+            _ => {
+                self.timer_period = (self.timer_period & 0x00ff) | (u16::from(value & 0x07) << 8);
+                self.length.load(value >> 3);
+                self.linear_counter_reload = true;
+            }
+        }
+    }
+
+    // Unlike the other channels, the triangle's timer runs at the CPU clock
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            // Periods below 2 are ultrasonic. Rather than alias into a
+            // buzz, the sequencer holds its position like it does while
+            // silenced.
+            if self.linear_counter > 0 && self.length.active() && self.timer_period >= 2 {
+                self.sequence_step = (self.sequence_step + 1) & 0x1f;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_linear_counter(&mut self) {
+        if self.linear_counter_reload {
+            self.linear_counter = self.linear_counter_load;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+
+        if !self.length.halt {
+            self.linear_counter_reload = false;
+        }
+    }
+
+    // Silencing the triangle stops its sequencer instead of muting it, so
+    // it keeps outputting the current step
+    fn output(&self) -> u8 {
+        TRIANGLE_SEQUENCE[usize::from(self.sequence_step)]
+    }
+}
+
+// NTSC noise periods, in CPU cycles
+const NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Noise {
+    // Its halt flag also loops the envelope
+    length: LengthCounter,
+    envelope: Envelope,
+    // Short mode taps bit 6 instead of bit 1, for a metallic 93-step loop
+    mode: bool,
+    period_index: u8,
+    timer: u16,
+    shift_register: u16,
+}
+
+impl Noise {
+    fn new() -> Self {
+        Self {
+            length: LengthCounter::default(),
+            envelope: Envelope::default(),
+            mode: false,
+            period_index: 0,
+            timer: 0,
+            // The shift register is loaded with 1 at power-up
+            shift_register: 1,
+        }
+    }
+
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.length.halt = value & 0x20 != 0;
+                self.envelope.write(value);
+            }
+            1 => {}
+            2 => {
+                self.mode = value & 0x80 != 0;
+                self.period_index = value & 0x0f;
+            }
+            _ => {
+                self.length.load(value >> 3);
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = NOISE_PERIODS[usize::from(self.period_index)] - 1;
+
+            let tap = if self.mode { 6 } else { 1 };
+            let feedback = (self.shift_register ^ (self.shift_register >> tap)) & 1;
+            self.shift_register = (self.shift_register >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if !self.length.active() || self.shift_register & 1 != 0 {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
+
+// The path from the 2A03 to the audio out jack: two high-pass filters at
+// 90Hz and 440Hz, then a low-pass at 14kHz. They are first-order RC filters,
+// run at the output sample rate.
+struct OutputFilters {
+    high_pass_90: HighPass,
+    high_pass_440: HighPass,
+    low_pass_14k: LowPass,
+}
+
+impl OutputFilters {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            high_pass_90: HighPass::new(sample_rate, 90.0),
+            high_pass_440: HighPass::new(sample_rate, 440.0),
+            low_pass_14k: LowPass::new(sample_rate, 14_000.0),
+        }
+    }
+
+    // The high-pass filters remove the mix's DC offset, leaving it centered
+    // around 0
+    fn process(&mut self, sample: f32) -> f32 {
+        let sample = self.high_pass_90.process(sample);
+        let sample = self.high_pass_440.process(sample);
+        self.low_pass_14k.process(sample)
+    }
+}
+
+// An RC filter's time constant over the sample period
+fn rc_ratio(sample_rate: u32, cutoff: f32) -> f32 {
+    let rc = 1.0 / (core::f32::consts::TAU * cutoff);
+    rc * sample_rate as f32
+}
+
+struct HighPass {
+    alpha: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl HighPass {
+    fn new(sample_rate: u32, cutoff: f32) -> Self {
+        let ratio = rc_ratio(sample_rate, cutoff);
+        Self {
+            alpha: ratio / (ratio + 1.0),
+            last_input: 0.0,
+            last_output: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        self.last_output = self.alpha * (self.last_output + sample - self.last_input);
+        self.last_input = sample;
+        self.last_output
+    }
+}
+
+struct LowPass {
+    alpha: f32,
+    last_output: f32,
+}
+
+impl LowPass {
+    fn new(sample_rate: u32, cutoff: f32) -> Self {
+        Self {
+            alpha: 1.0 / (rc_ratio(sample_rate, cutoff) + 1.0),
+            last_output: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        self.last_output += self.alpha * (sample - self.last_output);
+        self.last_output
+    }
+}
+
+// NTSC DMC periods, in CPU cycles
+const DMC_RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+// Plays 1-bit delta encoded samples straight from CPU memory. The reader
+// fetches a byte whenever the sample buffer is empty, and the output unit
+// shifts it out one bit per period, stepping the level up or down by 2.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Dmc {
+    irq_enabled: bool,
+    irq: bool,
+    looping: bool,
+    rate_index: u8,
+    timer: u16,
+    output_level: u8,
+    sample_address: u16,
+    sample_length: u16,
+    current_address: u16,
+    bytes_remaining: u16,
+    sample_buffer: Option<u8>,
+    shift_register: u8,
+    bits_remaining: u8,
+    silence: bool,
+}
+
+impl Dmc {
+    fn new() -> Self {
+        Self {
+            irq_enabled: false,
+            irq: false,
+            looping: false,
+            rate_index: 0,
+            timer: 0,
+            output_level: 0,
+            sample_address: 0xC000,
+            sample_length: 1,
+            current_address: 0xC000,
+            bytes_remaining: 0,
+            sample_buffer: None,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+        }
+    }
+
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.irq_enabled = value & 0x80 != 0;
+                if !self.irq_enabled {
+                    self.irq = false;
+                }
+                self.looping = value & 0x40 != 0;
+                self.rate_index = value & 0x0f;
+            }
+            1 => self.output_level = value & 0x7f,
+            // Samples live in $C000-$FFFF, in 64 byte steps
+            2 => self.sample_address = 0xC000 | (u16::from(value) << 6),
+            _ => self.sample_length = (u16::from(value) << 4) | 1,
+        }
+    }
+
+    // Any $4015 write acknowledges the interrupt. Enabling only restarts
+    // a sample that has finished.
+    fn set_enabled(&mut self, enabled: bool) {
+        self.irq = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    // Only the low bit of the output level survives a reset
+    fn reset(&mut self) {
+        self.output_level &= 0x01;
+    }
+
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    fn read_address(&self) -> Option<u16> {
+        (self.sample_buffer.is_none() && self.bytes_remaining > 0).then_some(self.current_address)
+    }
+
+    fn fill(&mut self, value: u8) {
+        self.sample_buffer = Some(value);
+        // The address wraps around to $8000
+        self.current_address = self.current_address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = DMC_RATES[usize::from(self.rate_index)] - 1;
+
+        if !self.silence {
+            if self.shift_register & 1 != 0 {
+                if self.output_level <= 125 {
+                    self.output_level += 2;
+                }
+            } else if self.output_level >= 2 {
+                self.output_level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.sample_buffer.take() {
+                Some(value) => {
+                    self.shift_register = value;
+                    self.silence = false;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+}
+
+// NTSC frame counter steps, in CPU cycles since the sequence started
+const QUARTER_FRAME_1: u64 = 7457;
+const HALF_FRAME_1: u64 = 14913;
+const QUARTER_FRAME_3: u64 = 22371;
+const FOUR_STEP_IRQ: u64 = 29829;
+const FIVE_STEP_HALF_FRAME: u64 = 37281;
+const FOUR_STEP_LENGTH: u64 = 29830;
+const FIVE_STEP_LENGTH: u64 = 37282;
+
+// Which units a frame counter step clocks: envelopes and the triangle's
+// linear counter every quarter frame, length counters and sweeps every
+// half frame
+#[derive(Default)]
+struct FrameClock {
+    quarter: bool,
+    half: bool,
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct FrameCounter {
+    five_step: bool,
+    irq_inhibit: bool,
+    irq: bool,
+    cycle: u64,
+    // A $4017 write restarts the sequence a few cycles later, switching
+    // to the mode it wrote only then
+    reset_delay: Option<u8>,
+    pending_five_step: bool,
+}
+
+impl FrameCounter {
+    fn write(&mut self, value: u8, cycles: u64) {
+        self.pending_five_step = value & 0x80 != 0;
+        self.irq_inhibit = value & 0x40 != 0;
+        if self.irq_inhibit {
+            self.irq = false;
+        }
+
+        // 3 cycles when written during an APU cycle, 4 in between
+        self.reset_delay = Some(if cycles & 1 == 0 { 3 } else { 4 });
+    }
+
+    // Like rewriting $4017 with the mode and inhibit flag it already has
+    fn reset(&mut self, cycles: u64) {
+        self.write(
+            u8::from(self.five_step) << 7 | u8::from(self.irq_inhibit) << 6,
+            cycles,
+        );
+        self.irq = false;
+    }
+
+    fn clock(&mut self) -> FrameClock {
+        let mut reset = false;
+        if let Some(delay) = self.reset_delay {
+            if delay == 0 {
+                self.reset_delay = None;
+                self.cycle = 0;
+                self.five_step = self.pending_five_step;
+                reset = true;
+            } else {
+                self.reset_delay = Some(delay - 1);
+            }
+        }
+
+        self.cycle += 1;
+
+        // The 5-step sequence clocks everything right as it starts
+        let half = (reset && self.five_step)
+            || match self.cycle {
+                HALF_FRAME_1 => true,
+                FOUR_STEP_IRQ => !self.five_step,
+                FIVE_STEP_HALF_FRAME => self.five_step,
+                _ => false,
+            };
+        let quarter = half || matches!(self.cycle, QUARTER_FRAME_1 | QUARTER_FRAME_3);
+
+        if !self.five_step
+            && (FOUR_STEP_IRQ - 1..=FOUR_STEP_LENGTH).contains(&self.cycle)
+            && !self.irq_inhibit
+        {
+            self.irq = true;
+        }
+
+        let length = if self.five_step {
+            FIVE_STEP_LENGTH
+        } else {
+            FOUR_STEP_LENGTH
+        };
+        if self.cycle >= length {
+            self.cycle = 0;
+        }
+
+        FrameClock { quarter, half }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Apu, Channels, Envelope, Noise, OutputFilters, Pulse, Triangle, CPU_CLOCK, FOUR_STEP_IRQ,
+        FOUR_STEP_LENGTH, FRAME_COUNTER, NOISE_PERIODS, STATUS,
+    };
+
+    #[test]
+    fn test_frame_irq() {
+        let mut apu = Apu::new();
+
+        for _ in 0..FOUR_STEP_IRQ - 2 {
+            apu.clock();
+        }
+        assert!(!apu.frame_irq());
+        apu.clock();
+        assert!(apu.frame_irq());
+
+        // Setting the inhibit flag clears the IRQ
+        apu.write(FRAME_COUNTER, 0x40);
+        assert!(!apu.frame_irq());
+        for _ in 0..2 * FOUR_STEP_IRQ {
+            apu.clock();
+        }
+        assert!(!apu.frame_irq());
+
+        // Once it takes over, the five step sequence never raises it
+        apu.write(FRAME_COUNTER, 0x80);
+        for _ in 0..5 {
+            apu.clock();
+        }
+        apu.read_status();
+        for _ in 0..2 * FOUR_STEP_IRQ {
+            apu.clock();
+        }
+        assert!(!apu.frame_irq());
+    }
+
+    #[test]
+    fn test_frame_counter_write_delay() {
+        // Cycles from a $4017 write until the frame IRQ
+        fn irq_delay(parity: u64) -> u64 {
+            let mut apu = Apu::new();
+            for _ in 0..parity {
+                apu.clock();
+            }
+            apu.write(FRAME_COUNTER, 0x00);
+            let mut cycles = 0;
+            while !apu.frame_irq() {
+                apu.clock();
+                cycles += 1;
+            }
+            cycles
+        }
+
+        // The sequence restarts 3 cycles after the write, or 4 when it
+        // lands between APU cycles
+        assert_eq!(irq_delay(0), 3 + FOUR_STEP_IRQ - 1);
+        assert_eq!(irq_delay(1), 4 + FOUR_STEP_IRQ - 1);
+
+        // The old sequence keeps running until then, in its old mode
+        let mut apu = Apu::new();
+        for _ in 0..FOUR_STEP_IRQ - 3 {
+            apu.clock();
+        }
+        apu.write(FRAME_COUNTER, 0x80);
+        apu.clock();
+        apu.clock();
+        assert!(apu.frame_irq());
+
+        // Starting the 5-step sequence clocks the length counters
+        let mut apu = Apu::new();
+        apu.write(STATUS, 0x01);
+        apu.write(0x4003, 0x00);
+        apu.write(FRAME_COUNTER, 0xc0);
+        for _ in 0..3 {
+            apu.clock();
+        }
+        assert_eq!(apu.pulse1.length.counter, 10);
+        apu.clock();
+        assert_eq!(apu.pulse1.length.counter, 9);
+    }
+
+    #[test]
+    fn test_pulse_duty() {
+        let mut apu = Apu::new();
+        apu.write(STATUS, 0x01);
+        // 50% duty, constant volume 15, period 16
+        apu.write(0x4000, 0xbf);
+        apu.write(0x4002, 0x10);
+        apu.write(0x4003, 0x00);
+
+        // Each of the 8 steps lasts (period + 1) APU cycles
+        let mut high = 0;
+        for _ in 0..8 * 17 * 2 {
+            apu.clock();
+            if apu.pulse1.output() == 15 {
+                high += 1;
+            }
+        }
+        assert_eq!(high, 4 * 17 * 2);
+    }
+
+    #[test]
+    fn test_pulse_sweep() {
+        let mut pulse1 = Pulse::new(true);
+        let mut pulse2 = Pulse::new(false);
+        for pulse in [&mut pulse1, &mut pulse2] {
+            pulse.write(2, 0x00);
+            pulse.write(3, 0x01);
+            // Enabled, negate, shift 1
+            pulse.write(1, 0x89);
+        }
+
+        assert_eq!(pulse1.sweep_target(), 0x7f);
+        assert_eq!(pulse2.sweep_target(), 0x80);
+
+        pulse1.clock_sweep();
+        assert_eq!(pulse1.timer_period, 0x7f);
+
+        // Periods below 8 mute the channel
+        pulse2.write(2, 0x07);
+        pulse2.write(3, 0x00);
+        assert!(pulse2.sweep_muted());
+
+        // So do targets past $7FF, even with the sweep disabled
+        pulse2.write(1, 0x01);
+        pulse2.write(2, 0x00);
+        pulse2.write(3, 0x06);
+        assert!(pulse2.sweep_muted());
+    }
+
+    #[test]
+    fn test_envelope() {
+        let mut envelope = Envelope::default();
+        envelope.write(0x00);
+        envelope.start = true;
+
+        envelope.clock(false);
+        assert_eq!(envelope.volume(), 15);
+        for _ in 0..15 {
+            envelope.clock(false);
+        }
+        assert_eq!(envelope.volume(), 0);
+
+        // Stays silent unless looping
+        envelope.clock(false);
+        assert_eq!(envelope.volume(), 0);
+        envelope.clock(true);
+        assert_eq!(envelope.volume(), 15);
+
+        envelope.write(0x17);
+        assert_eq!(envelope.volume(), 7);
+    }
+
+    #[test]
+    fn test_length_counter() {
+        let mut apu = Apu::new();
+
+        // Loads are ignored while the channel is disabled
+        apu.write(0x4003, 0x00);
+        assert_eq!(apu.pulse1.length.counter, 0);
+
+        apu.write(STATUS, 0x01);
+        apu.write(0x4003, 0x00);
+        assert_eq!(apu.pulse1.length.counter, 10);
+
+        // Clocked twice per 4-step sequence
+        for _ in 0..FOUR_STEP_LENGTH {
+            apu.clock();
+        }
+        assert_eq!(apu.pulse1.length.counter, 8);
+        for _ in 0..4 * FOUR_STEP_LENGTH {
+            apu.clock();
+        }
+        assert_eq!(apu.pulse1.length.counter, 0);
+        assert_eq!(apu.pulse1.output(), 0);
+
+        // Halted counters hold
+        apu.write(0x4000, 0x20);
+        apu.write(0x4003, 0x08);
+        for _ in 0..FOUR_STEP_LENGTH {
+            apu.clock();
+        }
+        assert_eq!(apu.pulse1.length.counter, 254);
+
+        // Disabling the channel clears it
+        apu.write(STATUS, 0x00);
+        assert_eq!(apu.pulse1.length.counter, 0);
+    }
+
+    #[test]
+    fn test_status() {
+        let mut apu = Apu::new();
+        apu.write(STATUS, 0x0f);
+        apu.write(0x4003, 0x08);
+        apu.write(0x400B, 0x08);
+        assert_eq!(apu.read_status(), 0x05);
+
+        // Reading acknowledges the frame interrupt
+        for _ in 0..FOUR_STEP_IRQ {
+            apu.clock();
+        }
+        assert_eq!(apu.peek_status(), 0x45);
+        assert_eq!(apu.read_status(), 0x45);
+        assert_eq!(apu.read_status(), 0x05);
+    }
+
+    #[test]
+    fn test_dmc() {
+        let mut apu = Apu::new();
+        // IRQ enabled, fastest rate, 17 bytes from $C040
+        apu.write(0x4010, 0x8f);
+        apu.write(0x4012, 0x01);
+        apu.write(0x4013, 0x01);
+        apu.write(STATUS, 0x10);
+        assert_eq!(apu.read_status(), 0x10);
+
+        for i in 0..17 {
+            assert_eq!(apu.dmc_read_address(), Some(0xC040 + i));
+            apu.dmc_fill(0xff);
+            // The output unit takes a byte every 8 periods
+            for _ in 0..8 * 54 {
+                apu.clock();
+            }
+        }
+        assert_eq!(apu.dmc_read_address(), None);
+        assert_eq!(apu.read_status(), 0x80);
+        // Each set bit raised the level by 2, up to the top of its range
+        assert_eq!(apu.dmc.output_level, 126);
+
+        // Writing $4015 acknowledges it
+        apu.write(STATUS, 0x00);
+        assert_eq!(apu.read_status(), 0x00);
+    }
+
+    #[test]
+    fn test_mute_solo() {
+        let mut apu = Apu::new();
+        apu.write(STATUS, 0x01);
+        // Pulse 1 at a constant volume of 15, on a high step of its duty
+        apu.write(0x4000, 0xff);
+        apu.write(0x4002, 0x10);
+        apu.write(0x4003, 0x00);
+        // The triangle sits on its first step, 15
+        let both = apu.output();
+        let triangle = 159.79 / (8227.0 / 15.0 + 100.0);
+        assert!(both > triangle);
+
+        apu.set_muted(Channels::PULSE1);
+        assert!((apu.output() - triangle).abs() < 1e-6);
+
+        apu.set_muted(Channels::empty());
+        apu.set_solo(Channels::PULSE1 | Channels::NOISE);
+        assert!((apu.output() - (both - triangle)).abs() < 1e-6);
+
+        // Muting wins over soloing
+        apu.set_muted(Channels::PULSE1);
+        assert_eq!(apu.output(), 0.0);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut apu = Apu::new();
+        apu.write(STATUS, 0x1f);
+        apu.write(0x4000, 0x84);
+        apu.write(0x4001, 0x9a);
+        apu.write(0x4002, 0x40);
+        apu.write(0x4003, 0x21);
+        apu.write(0x4008, 0x40);
+        apu.write(0x400A, 0x30);
+        apu.write(0x400B, 0x08);
+        apu.write(0x400C, 0x05);
+        apu.write(0x400E, 0x03);
+        apu.write(0x400F, 0x08);
+        for _ in 0..10_000 {
+            apu.clock();
+        }
+
+        let state = apu.snapshot();
+        let play = |apu: &mut Apu| {
+            (0..40_000)
+                .map(|_| {
+                    apu.clock();
+                    (apu.output(), apu.peek_status())
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = play(&mut apu);
+        apu.restore(&state);
+        assert_eq!(play(&mut apu), first);
+    }
+
+    #[test]
+    fn test_output_filters() {
+        // A held level fades away
+        let mut filters = OutputFilters::new(48_000);
+        let mut sample = 0.0;
+        for _ in 0..4800 {
+            sample = filters.process(0.5);
+        }
+        assert!(sample.abs() < 1e-3);
+
+        // A 2kHz square wave mostly makes it through
+        let mut filters = OutputFilters::new(48_000);
+        let peak = (0..4800)
+            .map(|i| filters.process(if i % 24 < 12 { 0.0 } else { 0.5 }))
+            .skip(2400)
+            .fold(0.0f32, |peak, sample| peak.max(sample));
+        assert!(peak > 0.2);
+
+        // Disabled, the mix is only recentered
+        let mut apu = Apu::new();
+        apu.set_sample_rate(48_000);
+        apu.set_filters_enabled(false);
+        for _ in 0..CPU_CLOCK / 10 {
+            apu.clock();
+        }
+        let mut samples = [0.0; 4800];
+        let count = apu.samples_mut().read_f32(&mut samples);
+        let triangle = 159.79 / (8227.0 / 15.0 + 100.0) * 2.0 - 1.0;
+        assert!((samples[count - 1] - triangle).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_vrc6() {
+        let mut apu = Apu::new();
+        apu.set_solo(Channels::EXPANSION);
+        // Ignored without the expansion
+        apu.write_expansion(0x9000, 0x0f);
+        apu.write_expansion(0x9002, 0x80);
+        assert_eq!(apu.output(), 0.0);
+
+        apu.set_vrc6_enabled(true);
+        // Pulse at volume 15, duty 4 of 16, period 1
+        apu.write_expansion(0x9000, 0x3f);
+        apu.write_expansion(0x9001, 0x01);
+        apu.write_expansion(0x9002, 0x80);
+        let high = (0..32)
+            .filter(|_| {
+                apu.clock();
+                apu.output() > 0.0
+            })
+            .count();
+        assert_eq!(high, 8);
+        apu.write_expansion(0x9002, 0x00);
+
+        // The sawtooth ramps up 6 times then resets, each step lasting two
+        // timer clocks
+        apu.write_expansion(0xB000, 0x08);
+        apu.write_expansion(0xB001, 0x00);
+        apu.write_expansion(0xB002, 0x80);
+        let levels: Vec<u8> = (0..14)
+            .map(|_| {
+                apu.clock();
+                apu.vrc6.as_ref().unwrap().output()
+            })
+            .collect();
+        assert_eq!(levels, [0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 0]);
+
+        // $9003 halts everything
+        apu.write_expansion(0x9003, 0x01);
+        apu.clock();
+        assert_eq!(apu.vrc6.as_ref().unwrap().output(), 0);
+        apu.clock();
+        assert_eq!(apu.vrc6.as_ref().unwrap().output(), 0);
+
+        // Registers are mirrored wherever A2-A11 are set
+        apu.write_expansion(0x9013, 0x00);
+        apu.write_expansion(0xB00E, 0x00);
+        apu.write_expansion(0xA7F0, 0x8f);
+        apu.write_expansion(0xA00A, 0x80);
+        apu.clock();
+        assert_eq!(apu.vrc6.as_ref().unwrap().output(), 15);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let mut triangle = Triangle::default();
+        triangle.length.set_enabled(true);
+        // Linear counter of 2, period 3
+        triangle.write(0, 0x02);
+        triangle.write(2, 0x03);
+        triangle.write(3, 0x00);
+
+        // Nothing plays until a quarter frame loads the linear counter
+        for _ in 0..8 {
+            triangle.clock_timer();
+        }
+        assert_eq!(triangle.output(), 15);
+
+        triangle.clock_linear_counter();
+        for _ in 0..4 * 3 {
+            triangle.clock_timer();
+        }
+        assert_eq!(triangle.output(), 12);
+
+        // The sequencer holds once the linear counter runs out
+        triangle.clock_linear_counter();
+        triangle.clock_linear_counter();
+        for _ in 0..8 {
+            triangle.clock_timer();
+        }
+        assert_eq!(triangle.output(), 12);
+
+        // Ultrasonic periods hold it too
+        triangle.write(2, 0x01);
+        triangle.write(3, 0x00);
+        triangle.clock_linear_counter();
+        for _ in 0..8 {
+            triangle.clock_timer();
+        }
+        assert_eq!(triangle.output(), 12);
+    }
+
+    #[test]
+    fn test_noise() {
+        // Counts the steps until the shift register repeats
+        fn sequence_length(mode: u8) -> usize {
+            let mut noise = Noise::new();
+            noise.write(2, mode);
+            for _ in 0..100 {
+                noise.clock_timer();
+            }
+
+            let start = noise.shift_register;
+            let mut steps = 0;
+            loop {
+                for _ in 0..NOISE_PERIODS[0] {
+                    noise.clock_timer();
+                }
+                steps += 1;
+                if noise.shift_register == start {
+                    return steps;
+                }
+            }
+        }
+
+        assert_eq!(sequence_length(0x00), 32767);
+        assert_eq!(sequence_length(0x80), 93);
+
+        let mut noise = Noise::new();
+        noise.length.set_enabled(true);
+        noise.write(0, 0x1a);
+        noise.write(3, 0x00);
+        // Bit 0 of the shift register mutes the output
+        noise.shift_register = 0x02;
+        assert_eq!(noise.output(), 10);
+        noise.shift_register = 0x01;
+        assert_eq!(noise.output(), 0);
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut apu = Apu::new();
+        for _ in 0..1000 {
+            apu.clock();
+        }
+        assert!(apu.samples_mut().is_empty());
+
+        apu.set_sample_rate(44_100);
+        for _ in 0..CPU_CLOCK {
+            apu.clock();
+        }
+        assert_eq!(apu.samples_mut().len(), 44_100);
+
+        // Frontends falling behind lose the oldest samples
+        for _ in 0..CPU_CLOCK / 2 {
+            apu.clock();
+        }
+        assert_eq!(apu.samples_mut().len(), 44_100);
+
+        apu.samples_mut().clear();
+        apu.adjust_sample_rate(44_000);
+        for _ in 0..CPU_CLOCK {
+            apu.clock();
+        }
+        assert_eq!(apu.samples_mut().len(), 44_000);
+    }
+}
@@ -0,0 +1,161 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Konami's VRC6 expansion audio: two pulse channels with 8 duty cycles
+/// and a sawtooth, mixed in with the APU through the cartridge connector.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Vrc6 {
+    pulse1: Vrc6Pulse,
+    pulse2: Vrc6Pulse,
+    sawtooth: Sawtooth,
+    halt: bool,
+    // $9003 speeds every timer up 16 or 256 times
+    period_shift: u8,
+}
+
+impl Vrc6 {
+    /// Handles writes to $9000-$9003, $A000-$A002 and $B000-$B002, as seen
+    /// by a mapper 24 board.
+    pub(crate) fn write(&mut self, address: u16, value: u8) {
+        // Only A0, A1 and A12-A15 are decoded
+        let address = address & 0xf003;
+        match address {
+            0x9003 => {
+                self.halt = value & 0x01 != 0;
+                self.period_shift = if value & 0x04 != 0 {
+                    8
+                } else if value & 0x02 != 0 {
+                    4
+                } else {
+                    0
+                };
+            }
+            0x9000..=0x9002 => self.pulse1.write(address & 0x03, value),
+            0xA000..=0xA002 => self.pulse2.write(address & 0x03, value),
+            0xB000..=0xB002 => self.sawtooth.write(address & 0x03, value),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn clock(&mut self) {
+        if self.halt {
+            return;
+        }
+        self.pulse1.clock(self.period_shift);
+        self.pulse2.clock(self.period_shift);
+        self.sawtooth.clock(self.period_shift);
+    }
+
+    /// The sum of the channels, between 0 and 61.
+    pub(crate) fn output(&self) -> u8 {
+        self.pulse1.output() + self.pulse2.output() + self.sawtooth.output()
+    }
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Vrc6Pulse {
+    enabled: bool,
+    // Ignores the duty and outputs the volume constantly
+    digitized: bool,
+    duty: u8,
+    volume: u8,
+    timer_period: u16,
+    timer: u16,
+    step: u8,
+}
+
+impl Vrc6Pulse {
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.digitized = value & 0x80 != 0;
+                self.duty = (value >> 4) & 0x07;
+                self.volume = value & 0x0f;
+            }
+            1 => self.timer_period = (self.timer_period & 0x0f00) | u16::from(value),
+            _ => {
+                self.timer_period = (self.timer_period & 0x00ff) | (u16::from(value & 0x0f) << 8);
+                self.enabled = value & 0x80 != 0;
+                // Disabling resets the duty cycle
+                if !self.enabled {
+                    self.step = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer == 0 {
+            self.timer = self.timer_period >> shift;
+            self.step = (self.step + 1) & 0x0f;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.digitized || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Sawtooth {
+    enabled: bool,
+    rate: u8,
+    timer_period: u16,
+    timer: u16,
+    // Counts 14 timer clocks, the accumulator grows on every other one
+    step: u8,
+    accumulator: u8,
+}
+
+impl Sawtooth {
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => self.rate = value & 0x3f,
+            1 => self.timer_period = (self.timer_period & 0x0f00) | u16::from(value),
+            _ => {
+                self.timer_period = (self.timer_period & 0x00ff) | (u16::from(value & 0x0f) << 8);
+                self.enabled = value & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.timer_period >> shift;
+
+        self.step += 1;
+        if self.step == 14 {
+            self.step = 0;
+            self.accumulator = 0;
+        } else if self.step & 1 == 0 {
+            self.accumulator = self.accumulator.wrapping_add(self.rate);
+        }
+    }
+
+    // The top 5 bits of the accumulator
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
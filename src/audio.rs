@@ -0,0 +1,319 @@
+use alloc::collections::VecDeque;
+
+#[cfg(feature = "cpal")]
+mod cpal;
+
+#[cfg(feature = "cpal")]
+pub use self::cpal::{AudioError, CpalOutput};
+
+// CoreAudio and WASAPI keep up with short buffers, browsers need the most
+// headroom, and ALSA/PulseAudio setups vary enough to want some
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const DEFAULT_LATENCY_MS: u32 = 40;
+#[cfg(target_os = "windows")]
+const DEFAULT_LATENCY_MS: u32 = 50;
+#[cfg(target_arch = "wasm32")]
+const DEFAULT_LATENCY_MS: u32 = 100;
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows",
+    target_arch = "wasm32"
+)))]
+const DEFAULT_LATENCY_MS: u32 = 80;
+
+/// How an audio backend should open its output. Higher latency is more
+/// resistant to crackling when frames take uneven time, lower latency
+/// makes sound follow the picture more closely.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AudioConfig {
+    /// How much audio may be queued ahead of playback, in milliseconds.
+    pub latency_ms: u32,
+    /// The rate to open the device at, or its default rate.
+    pub sample_rate: Option<u32>,
+    /// The channel count to open the device with, or its default. The mono
+    /// output is copied to every channel.
+    pub channels: Option<u16>,
+}
+
+impl AudioConfig {
+    /// `latency_ms` in samples at `sample_rate`.
+    pub fn latency_samples(&self, sample_rate: u32) -> usize {
+        sample_rate as usize * self.latency_ms as usize / 1000
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            latency_ms: DEFAULT_LATENCY_MS,
+            sample_rate: None,
+            channels: None,
+        }
+    }
+}
+
+/// Something that plays, records or forwards the emulator's audio.
+pub trait AudioSink {
+    /// The rate the sink expects samples at.
+    fn sample_rate(&self) -> u32;
+
+    /// Takes mono samples between -1.0 and 1.0.
+    fn write(&mut self, samples: &[f32]);
+}
+
+// How far RateControl may move the sample rate, small enough that the
+// pitch change can't be heard
+const MAX_RATE_DEVIATION: f64 = 0.005;
+
+/// Dynamic rate control. The emulator runs in step with the display, which
+/// is rarely exactly at the NES's 60.0988Hz, so the audio device drains
+/// samples slightly faster or slower than they are made. Nudging the rate
+/// towards a target fill level keeps the device's queue from running dry
+/// or growing latency over long sessions.
+pub struct RateControl {
+    sample_rate: u32,
+    target: usize,
+}
+
+impl RateControl {
+    /// Controls around the device's `sample_rate`, aiming to keep `target`
+    /// samples queued.
+    pub fn new(sample_rate: u32, target: usize) -> Self {
+        Self {
+            sample_rate,
+            target: target.max(1),
+        }
+    }
+
+    /// The rate to generate at while `queued` samples wait to be played.
+    pub fn rate(&self, queued: usize) -> u32 {
+        let error = (self.target as f64 - queued as f64) / self.target as f64;
+        let adjust = 1.0 + MAX_RATE_DEVIATION * error.clamp(-1.0, 1.0);
+        (f64::from(self.sample_rate) * adjust + 0.5) as u32
+    }
+}
+
+/// An audio output frontends can swap in, such as cpal or a browser's
+/// audio context. Every backend is also an `AudioSink`.
+pub trait AudioBackend {
+    type Error;
+
+    /// Opens the output and starts playing.
+    fn init(config: &AudioConfig) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    /// The rate the output plays at.
+    fn sample_rate(&self) -> u32;
+
+    /// Queues mono samples between -1.0 and 1.0 for playback.
+    fn queue_samples(&mut self, samples: &[f32]);
+
+    /// The number of frames queued but not played yet, for `RateControl`.
+    fn buffered_frames(&self) -> usize;
+
+    fn pause(&mut self) -> Result<(), Self::Error>;
+
+    fn resume(&mut self) -> Result<(), Self::Error>;
+}
+
+impl<T: AudioBackend> AudioSink for T {
+    fn sample_rate(&self) -> u32 {
+        AudioBackend::sample_rate(self)
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        self.queue_samples(samples);
+    }
+}
+
+/// Mono samples waiting to be pulled by a frontend. Once full, the oldest
+/// samples are dropped to make room.
+pub struct AudioBuffer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl AudioBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Moves up to `out.len()` samples into `out`, returning how many.
+    pub fn read_f32(&mut self, out: &mut [f32]) -> usize {
+        let count = out.len().min(self.samples.len());
+        for (slot, sample) in out.iter_mut().zip(self.samples.drain(..count)) {
+            *slot = sample;
+        }
+        count
+    }
+
+    /// Like `read_f32`, scaled to the full range of `i16`.
+    pub fn read_i16(&mut self, out: &mut [i16]) -> usize {
+        let count = out.len().min(self.samples.len());
+        for (slot, sample) in out.iter_mut().zip(self.samples.drain(..count)) {
+            *slot = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        }
+        count
+    }
+
+    /// Hands every buffered sample to `sink`.
+    pub fn drain_to(&mut self, sink: &mut impl AudioSink) {
+        let (front, back) = self.samples.as_slices();
+        sink.write(front);
+        sink.write(back);
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioBackend, AudioBuffer, AudioConfig, AudioSink, RateControl};
+
+    struct Recorder(Vec<f32>);
+
+    impl AudioSink for Recorder {
+        fn sample_rate(&self) -> u32 {
+            44_100
+        }
+
+        fn write(&mut self, samples: &[f32]) {
+            self.0.extend_from_slice(samples);
+        }
+    }
+
+    #[test]
+    fn test_audio_buffer() {
+        let mut buffer = AudioBuffer::new(4);
+        for sample in [0.1, 0.2, 0.3, 0.4, 0.5, 1.0] {
+            buffer.push(sample);
+        }
+        // The oldest samples make room
+        assert_eq!(buffer.len(), 4);
+
+        let mut out = [0.0; 3];
+        assert_eq!(buffer.read_f32(&mut out), 3);
+        assert_eq!(out, [0.3, 0.4, 0.5]);
+
+        buffer.push(-1.0);
+        let mut out = [0; 3];
+        assert_eq!(buffer.read_i16(&mut out), 2);
+        assert_eq!(out[..2], [i16::MAX, -i16::MAX]);
+        assert!(buffer.is_empty());
+
+        let mut recorder = Recorder(Vec::new());
+        buffer.push(0.25);
+        buffer.push(0.75);
+        buffer.drain_to(&mut recorder);
+        assert_eq!(recorder.0, [0.25, 0.75]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_rate_control() {
+        let control = RateControl::new(48_000, 2_400);
+
+        assert_eq!(control.rate(2_400), 48_000);
+        // Running dry speeds up, overfilling slows down
+        assert_eq!(control.rate(0), 48_240);
+        assert_eq!(control.rate(1_200), 48_120);
+        assert_eq!(control.rate(3_600), 47_880);
+        // By no more than half a percent
+        assert_eq!(control.rate(100_000), 47_760);
+    }
+
+    #[test]
+    fn test_audio_config() {
+        let config = AudioConfig {
+            latency_ms: 50,
+            ..AudioConfig::default()
+        };
+        assert_eq!(config.latency_samples(48_000), 2_400);
+        assert_eq!(config.latency_samples(44_100), 2_205);
+    }
+
+    #[test]
+    fn test_audio_backend() {
+        struct Null {
+            sample_rate: u32,
+            queued: usize,
+            paused: bool,
+        }
+
+        impl AudioBackend for Null {
+            type Error = ();
+
+            fn init(config: &AudioConfig) -> Result<Self, ()> {
+                Ok(Self {
+                    sample_rate: config.sample_rate.unwrap_or(48_000),
+                    queued: 0,
+                    paused: false,
+                })
+            }
+
+            fn sample_rate(&self) -> u32 {
+                self.sample_rate
+            }
+
+            fn queue_samples(&mut self, samples: &[f32]) {
+                self.queued += samples.len();
+            }
+
+            fn buffered_frames(&self) -> usize {
+                self.queued
+            }
+
+            fn pause(&mut self) -> Result<(), ()> {
+                self.paused = true;
+                Ok(())
+            }
+
+            fn resume(&mut self) -> Result<(), ()> {
+                self.paused = false;
+                Ok(())
+            }
+        }
+
+        let mut backend = Null::init(&AudioConfig::default()).unwrap();
+        assert_eq!(AudioSink::sample_rate(&backend), 48_000);
+
+        // Backends take samples like any other sink
+        let mut buffer = AudioBuffer::new(16);
+        for _ in 0..10 {
+            buffer.push(0.0);
+        }
+        buffer.drain_to(&mut backend);
+        assert_eq!(backend.buffered_frames(), 10);
+
+        backend.pause().unwrap();
+        assert!(backend.paused);
+        backend.resume().unwrap();
+        assert!(!backend.paused);
+    }
+}
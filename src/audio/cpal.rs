@@ -0,0 +1,188 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SizedSample,
+};
+use log::warn;
+
+use super::{AudioBackend, AudioConfig};
+
+#[derive(Debug)]
+pub enum AudioError {
+    NoDevice,
+    Config(cpal::DefaultStreamConfigError),
+    SupportedConfigs(cpal::SupportedStreamConfigsError),
+    UnsupportedConfig,
+    Build(cpal::BuildStreamError),
+    Play(cpal::PlayStreamError),
+    Pause(cpal::PauseStreamError),
+    UnsupportedFormat(cpal::SampleFormat),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::NoDevice => write!(f, "no audio output device"),
+            AudioError::Config(e) => write!(f, "no usable output config: {}", e),
+            AudioError::SupportedConfigs(e) => write!(f, "failed to list output configs: {}", e),
+            AudioError::UnsupportedConfig => {
+                write!(f, "the output device doesn't support the requested config")
+            }
+            AudioError::Build(e) => write!(f, "failed to open output stream: {}", e),
+            AudioError::Play(e) => write!(f, "failed to start output stream: {}", e),
+            AudioError::Pause(e) => write!(f, "failed to pause output stream: {}", e),
+            AudioError::UnsupportedFormat(format) => {
+                write!(f, "unsupported sample format: {}", format)
+            }
+        }
+    }
+}
+
+impl Error for AudioError {}
+
+/// Plays the emulator's audio on the default cpal output device.
+pub struct CpalOutput {
+    // Playback stops when the stream is dropped
+    stream: cpal::Stream,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+    channels: u16,
+    // Samples queued past this are dropped, so a producer running ahead of
+    // real time doesn't build up latency
+    max_queued: usize,
+}
+
+impl CpalOutput {
+    pub fn new(config: &AudioConfig) -> Result<Self, AudioError> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(AudioError::NoDevice)?;
+        let supported = supported_config(&device, config)?;
+        let sample_rate = supported.sample_rate().0;
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+
+        let mut stream_config = supported.config();
+        // A quarter of the latency in the device's own buffer leaves the
+        // rest for the queue to absorb uneven frame times
+        if let cpal::SupportedBufferSize::Range { min, max } = *supported.buffer_size() {
+            let frames = config.latency_samples(sample_rate) as u32 / 4;
+            stream_config.buffer_size = cpal::BufferSize::Fixed(frames.clamp(min, max));
+        }
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => {
+                build_stream::<f32>(&device, &stream_config, Arc::clone(&queue))
+            }
+            cpal::SampleFormat::I16 => {
+                build_stream::<i16>(&device, &stream_config, Arc::clone(&queue))
+            }
+            cpal::SampleFormat::U16 => {
+                build_stream::<u16>(&device, &stream_config, Arc::clone(&queue))
+            }
+            format => return Err(AudioError::UnsupportedFormat(format)),
+        }?;
+        stream.play().map_err(AudioError::Play)?;
+
+        Ok(Self {
+            stream,
+            queue,
+            sample_rate,
+            channels: stream_config.channels,
+            max_queued: config.latency_samples(sample_rate),
+        })
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+impl AudioBackend for CpalOutput {
+    type Error = AudioError;
+
+    fn init(config: &AudioConfig) -> Result<Self, AudioError> {
+        Self::new(config)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn queue_samples(&mut self, samples: &[f32]) {
+        let mut queue = self.queue.lock().unwrap();
+        let room = self.max_queued.saturating_sub(queue.len());
+        queue.extend(samples.iter().take(room));
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    fn pause(&mut self) -> Result<(), AudioError> {
+        self.stream.pause().map_err(AudioError::Pause)
+    }
+
+    fn resume(&mut self) -> Result<(), AudioError> {
+        self.stream.play().map_err(AudioError::Play)
+    }
+}
+
+// The device's default config, unless a sample rate or channel count was
+// asked for
+fn supported_config(
+    device: &cpal::Device,
+    config: &AudioConfig,
+) -> Result<cpal::SupportedStreamConfig, AudioError> {
+    let default = device.default_output_config().map_err(AudioError::Config)?;
+    if config.sample_rate.is_none() && config.channels.is_none() {
+        return Ok(default);
+    }
+
+    let sample_rate = config.sample_rate.unwrap_or(default.sample_rate().0);
+    let channels = config.channels.unwrap_or(default.channels());
+    device
+        .supported_output_configs()
+        .map_err(AudioError::SupportedConfigs)?
+        .find(|range| {
+            range.channels() == channels
+                && (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&sample_rate)
+        })
+        .map(|range| range.with_sample_rate(cpal::SampleRate(sample_rate)))
+        .ok_or(AudioError::UnsupportedConfig)
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream, AudioError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = usize::from(config.channels);
+    // Repeats the last sample on underruns instead of popping to silence
+    let mut last = 0.0;
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut queue = queue.lock().unwrap();
+                for frame in data.chunks_mut(channels) {
+                    if let Some(sample) = queue.pop_front() {
+                        last = sample;
+                    }
+                    frame.fill(T::from_sample(last));
+                }
+            },
+            |e| warn!("Audio stream error: {}", e),
+            None,
+        )
+        .map_err(AudioError::Build)
+}
@@ -0,0 +1,145 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::f64::consts::{PI, TAU};
+
+// Each amplitude change is spread over this many output samples either side
+const HALF_WIDTH: usize = 8;
+const WIDTH: usize = 2 * HALF_WIDTH;
+const PHASES: u64 = 32;
+// Fraction of the output Nyquist frequency the kernel passes
+const CUTOFF: f64 = 0.9;
+
+/// Band-limited step synthesis in the style of blip_buf. Amplitude changes
+/// clocked in at the CPU rate are added to the output as windowed sinc
+/// steps, instead of point sampled, so they don't alias.
+pub(crate) struct BlipBuffer {
+    clock_rate: u32,
+    sample_rate: u32,
+    // Position between the current output sample and the next, in units of
+    // 1 / clock_rate samples
+    offset: u32,
+    amplitude: f32,
+    integrator: f32,
+    // Differences waiting to be summed into output samples. The current
+    // position sits between indices HALF_WIDTH - 1 and HALF_WIDTH
+    deltas: VecDeque<f32>,
+    kernel: Vec<[f32; WIDTH]>,
+}
+
+impl BlipBuffer {
+    pub(crate) fn new(clock_rate: u32, sample_rate: u32) -> Self {
+        Self {
+            clock_rate,
+            sample_rate,
+            offset: 0,
+            amplitude: 0.0,
+            integrator: 0.0,
+            deltas: VecDeque::from(vec![0.0; WIDTH]),
+            kernel: (0..PHASES).map(kernel_phase).collect(),
+        }
+    }
+
+    /// Changes the output rate, keeping the samples in flight.
+    pub(crate) fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Clocks one input cycle at `amplitude`, returning the next output
+    /// sample once it is complete.
+    pub(crate) fn clock(&mut self, amplitude: f32) -> Option<f32> {
+        let delta = amplitude - self.amplitude;
+        if delta != 0.0 {
+            self.amplitude = amplitude;
+            let phase = u64::from(self.offset) * PHASES / u64::from(self.clock_rate);
+            for (slot, weight) in self.deltas.iter_mut().zip(self.kernel[phase as usize]) {
+                *slot += delta * weight;
+            }
+        }
+
+        self.offset += self.sample_rate;
+        if self.offset >= self.clock_rate {
+            self.offset -= self.clock_rate;
+            self.integrator += self.deltas.pop_front().unwrap_or(0.0);
+            self.deltas.push_back(0.0);
+            Some(self.integrator)
+        } else {
+            None
+        }
+    }
+}
+
+// The step's derivative, a windowed sinc, sampled at the output rate for a
+// change `phase / PHASES` of a sample past the current position
+fn kernel_phase(phase: u64) -> [f32; WIDTH] {
+    let mut weights = [0.0; WIDTH];
+    let fraction = phase as f64 / PHASES as f64;
+    for (tap, weight) in weights.iter_mut().enumerate() {
+        let distance = tap as f64 - (HALF_WIDTH - 1) as f64 - fraction;
+        let x = distance * CUTOFF;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            sin(PI * x) / (PI * x)
+        };
+        // Blackman window
+        let w = distance / HALF_WIDTH as f64;
+        let window = 0.42 + 0.5 * cos(PI * w) + 0.08 * cos(TAU * w);
+        *weight = (sinc * window) as f32;
+    }
+
+    // Each step must add up to exactly its delta
+    let total: f32 = weights.iter().sum();
+    weights.iter_mut().for_each(|weight| *weight /= total);
+    weights
+}
+
+// core has no trigonometry, the Taylor series is plenty for building the
+// kernel once
+fn sin(x: f64) -> f64 {
+    let mut x = x % TAU;
+    if x > PI {
+        x -= TAU;
+    } else if x < -PI {
+        x += TAU;
+    }
+
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..12 {
+        term *= -x * x / ((2 * n) as f64 * (2 * n + 1) as f64);
+        sum += term;
+    }
+    sum
+}
+
+fn cos(x: f64) -> f64 {
+    sin(x + PI / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sin, BlipBuffer};
+    use core::f64::consts::PI;
+
+    #[test]
+    fn test_sin() {
+        for i in -40..40 {
+            let x = f64::from(i) * 0.37;
+            assert!((sin(x) - x.sin()).abs() < 1e-9);
+        }
+        assert!(sin(PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut blip = BlipBuffer::new(1_789_773, 48_000);
+        let samples: Vec<f32> = (0..3000)
+            .filter_map(|cycle| blip.clock(if cycle < 1000 { 0.0 } else { 0.5 }))
+            .collect();
+        assert_eq!(samples.len(), 80);
+
+        // The step is spread over a few samples instead of jumping
+        assert!(samples.iter().any(|&s| s > 0.05 && s < 0.45));
+        assert!(samples.iter().all(|&s| s > -0.05 && s < 0.55));
+        assert!((samples.last().unwrap() - 0.5).abs() < 1e-4);
+    }
+}
@@ -1,18 +1,46 @@
-use std::{cell::RefCell, rc::Rc};
+use alloc::rc::Rc;
+use core::cell::RefCell;
 
 pub trait Bus {
-    fn read(&self, address: u16) -> u8;
+    // Reads without side effects, for debuggers and traces
+    fn peek(&self, address: u16) -> u8;
+
+    // Reads as the CPU does. Devices whose registers have read side effects
+    // override this.
+    fn read(&mut self, address: u16) -> u8 {
+        self.peek(address)
+    }
+
     fn write(&mut self, address: u16, value: u8);
 
-    fn read16(&self, address: u16) -> u16 {
-        let lo = u16::from(self.read(address));
-        let hi = u16::from(self.read(address + 1));
-        return (hi << 8) | lo;
+    fn peek16(&self, address: u16) -> u16 {
+        let lo = u16::from(self.peek(address));
+        let hi = u16::from(self.peek(address.wrapping_add(1)));
+        (hi << 8) | lo
+    }
+
+    // Called once for every CPU cycle
+    fn tick(&mut self) {}
+
+    // State of the IRQ line driven by devices on the bus
+    fn irq(&self) -> bool {
+        false
+    }
+
+    // Current PPU scanline and dot, if there is a PPU on the bus
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    // Takes the page of a pending OAM DMA. The CPU performs the copy, since
+    // it is halted while the DMA unit drives the bus.
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        None
     }
 }
 
 impl Bus for [u8; 65536] {
-    fn read(&self, address: u16) -> u8 {
+    fn peek(&self, address: u16) -> u8 {
         self[address as usize]
     }
 
@@ -22,21 +50,61 @@ impl Bus for [u8; 65536] {
 }
 
 impl<B: Bus> Bus for Rc<RefCell<B>> {
-    fn read(&self, address: u16) -> u8 {
-        self.borrow().read(address)
+    fn peek(&self, address: u16) -> u8 {
+        self.borrow().peek(address)
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        self.borrow_mut().read(address)
     }
 
     fn write(&mut self, address: u16, value: u8) {
         self.borrow_mut().write(address, value)
     }
+
+    fn tick(&mut self) {
+        self.borrow_mut().tick()
+    }
+
+    fn irq(&self) -> bool {
+        self.borrow().irq()
+    }
+
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        self.borrow().ppu_position()
+    }
+
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        self.borrow_mut().take_oam_dma()
+    }
 }
 
 impl Bus for Rc<RefCell<dyn Bus>> {
-    fn read(&self, address: u16) -> u8 {
-        self.borrow().read(address)
+    fn peek(&self, address: u16) -> u8 {
+        self.borrow().peek(address)
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        self.borrow_mut().read(address)
     }
 
     fn write(&mut self, address: u16, value: u8) {
         self.borrow_mut().write(address, value)
     }
+
+    fn tick(&mut self) {
+        self.borrow_mut().tick()
+    }
+
+    fn irq(&self) -> bool {
+        self.borrow().irq()
+    }
+
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        self.borrow().ppu_position()
+    }
+
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        self.borrow_mut().take_oam_dma()
+    }
 }
@@ -1,58 +1,463 @@
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{error::Error, fmt};
+
 use crate::bus::Bus;
 
+use self::axrom::Axrom;
+use self::fme7::Fme7;
+use self::gxrom::Gxrom;
+use self::header::{HEADER_LEN, TRAINER_LEN};
+use self::mmc2::Mmc2;
+use self::mmc3::Mmc3;
+use self::nrom::Nrom;
+use self::vrc4::{Vrc4, Vrc4Variant};
+use self::vrc6::Vrc6;
+
+pub use self::header::{ConsoleType, Header, Timing};
+#[cfg(feature = "std")]
+pub use self::save::sav_path;
+
+mod axrom;
+mod fme7;
+mod gxrom;
+mod header;
+mod mmc2;
+mod mmc3;
+mod nrom;
+#[cfg(feature = "std")]
+mod save;
+mod vrc4;
+pub(crate) mod vrc6;
+mod vrc_irq;
+
+const PRG_BANK_LEN: usize = 0x2000;
+
+/// Why a ROM file couldn't be loaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CartridgeError {
+    /// The file doesn't start with "NES\x1A".
+    InvalidMagic,
+    /// The header says there is no PRG ROM.
+    NoPrgRom,
+    /// The file is shorter than its header says it should be.
+    Truncated {
+        expected: usize,
+        actual: usize,
+    },
+    UnsupportedMapper(u16),
+    /// The PRG ROM isn't a whole number of the mapper's banks.
+    PrgRomSize {
+        mapper: u16,
+        len: usize,
+    },
+    /// Save RAM doesn't match the size of the cartridge's battery-backed RAM.
+    SaveRamSize {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::InvalidMagic => write!(f, "not an iNES file"),
+            CartridgeError::NoPrgRom => write!(f, "the header declares no PRG ROM"),
+            CartridgeError::Truncated { expected, actual } => {
+                write!(
+                    f,
+                    "the header declares {} bytes but the file has {}",
+                    expected, actual
+                )
+            }
+            CartridgeError::UnsupportedMapper(mapper) => {
+                write!(f, "unsupported mapper {}", mapper)
+            }
+            CartridgeError::PrgRomSize { mapper, len } => {
+                write!(
+                    f,
+                    "{} bytes of PRG ROM don't fit the banks of mapper {}",
+                    len, mapper
+                )
+            }
+            CartridgeError::SaveRamSize { expected, actual } => {
+                write!(
+                    f,
+                    "the cartridge has {} bytes of save RAM but got {}",
+                    expected, actual
+                )
+            }
+        }
+    }
+}
+
+impl Error for CartridgeError {}
+
+/// How the PPU's two nametables are laid out across its four.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    SingleScreenLower,
+    SingleScreenUpper,
+    FourScreen,
+}
+
+/// A sound chip on the cartridge, mixed in by the APU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExpansionAudio {
+    /// Konami's VRC6. Mapper 26 boards swap A0 and A1 of its registers.
+    Vrc6 { swapped_lines: bool },
+}
+
+/// The hardware on a cartridge board that maps its ROM and RAM into the
+/// CPU's $4020-$FFFF and the PPU's $0000-$1FFF.
+pub trait Mapper {
+    fn cpu_read(&self, address: u16) -> u8;
+
+    fn cpu_write(&mut self, address: u16, value: u8);
+
+    /// Reads pattern tables. Some boards watch these reads to switch banks.
+    fn chr_read(&mut self, address: u16) -> u8;
+
+    fn chr_write(&mut self, address: u16, value: u8);
+
+    fn mirroring(&self) -> Mirroring;
+
+    /// Called once for every CPU cycle, for boards with cycle counters.
+    fn clock(&mut self) {}
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio> {
+        None
+    }
+
+    /// The RAM at $6000-$7FFF, if the board has any.
+    fn prg_ram(&self) -> &[u8] {
+        &[]
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut []
+    }
+
+    /// Whether the board is holding the CPU's IRQ line.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+}
+
+// The sections of a ROM file, for mappers to build themselves from
+pub(crate) struct RomImage {
+    pub(crate) prg_rom: Vec<u8>,
+    // Empty for boards with CHR RAM
+    pub(crate) chr_rom: Vec<u8>,
+    // Volatile and battery-backed RAM together
+    pub(crate) prg_ram_len: usize,
+    pub(crate) chr_ram_len: usize,
+    pub(crate) mirroring: Mirroring,
+}
+
+impl RomImage {
+    // Every board switches PRG ROM in banks of at least 8KB, and needs as
+    // many as it always maps
+    fn fit(self, mapper: u16, min_banks: usize) -> Result<Self, CartridgeError> {
+        let len = self.prg_rom.len();
+        if len < min_banks * PRG_BANK_LEN || !len.is_multiple_of(PRG_BANK_LEN) {
+            return Err(CartridgeError::PrgRomSize { mapper, len });
+        }
+        Ok(self)
+    }
+
+    /// Takes the CHR ROM, or allocates CHR RAM on boards without any. The
+    /// flag is set for RAM.
+    pub(crate) fn take_chr(&mut self) -> (Vec<u8>, bool) {
+        if self.chr_rom.is_empty() {
+            (vec![0; self.chr_ram_len.max(1)], true)
+        } else {
+            (core::mem::take(&mut self.chr_rom), false)
+        }
+    }
+
+    // Every bank is filled with its own number
+    #[cfg(test)]
+    pub(crate) fn banked(
+        prg_banks: usize,
+        prg_bank_len: usize,
+        chr_banks: usize,
+        chr_bank_len: usize,
+    ) -> Self {
+        let fill =
+            |banks: usize, len: usize| (0..banks).flat_map(|bank| vec![bank as u8; len]).collect();
+        Self {
+            prg_rom: fill(prg_banks, prg_bank_len),
+            chr_rom: fill(chr_banks, chr_bank_len),
+            prg_ram_len: 0x2000,
+            chr_ram_len: 0,
+            mirroring: Mirroring::Vertical,
+        }
+    }
+}
+
 pub struct Cartridge {
-    cartridge_ram: [u8; 0x2000],
-    prg_rom: Vec<u8>,
+    mapper: Box<dyn Mapper>,
+    header: Option<Header>,
 }
 
 impl Cartridge {
-    pub fn from_rom(buffer: &[u8]) -> Self {
-        // TODO: Check NES header
-        // TODO: Check iNes 1.0 format
+    pub fn from_rom(buffer: &[u8]) -> Result<Self, CartridgeError> {
+        let header = Header::parse(buffer)?;
 
-        let skip_trainer = buffer[6] & 0b100 != 0;
+        let expected = header.file_len();
+        if buffer.len() < expected {
+            return Err(CartridgeError::Truncated {
+                expected,
+                actual: buffer.len(),
+            });
+        }
 
-        let prg_rom_start = 16 + if skip_trainer { 512 } else { 0 };
-        let prg_rom_end = prg_rom_start + buffer[4] as usize * 0x4000;
+        let prg_rom_start = HEADER_LEN + if header.trainer { TRAINER_LEN } else { 0 };
+        let prg_rom_end = prg_rom_start + header.prg_rom_len;
+        let chr_rom_end = prg_rom_end + header.chr_rom_len;
 
-        // TODO: read chr rom
-        Self {
-            cartridge_ram: [0x00; 0x2000],
+        let rom = RomImage {
             prg_rom: buffer[prg_rom_start..prg_rom_end].to_vec(),
+            chr_rom: buffer[prg_rom_end..chr_rom_end].to_vec(),
+            prg_ram_len: header.prg_ram_len + header.prg_nvram_len,
+            chr_ram_len: header.chr_ram_len + header.chr_nvram_len,
+            mirroring: header.mirroring,
+        };
+
+        let number = header.mapper;
+        let mapper: Box<dyn Mapper> = match number {
+            0 => Box::new(Nrom::new(rom.fit(number, Nrom::MIN_PRG_BANKS)?)),
+            4 => Box::new(Mmc3::new(rom.fit(number, Mmc3::MIN_PRG_BANKS)?)),
+            7 => Box::new(Axrom::new(rom.fit(number, Axrom::MIN_PRG_BANKS)?)),
+            9 => Box::new(Mmc2::new(rom.fit(number, Mmc2::MIN_PRG_BANKS)?)),
+            21 | 22 | 23 | 25 => Box::new(Vrc4::new(
+                rom.fit(number, Vrc4::MIN_PRG_BANKS)?,
+                Vrc4Variant::new(number, header.submapper),
+            )),
+            24 => Box::new(Vrc6::new(rom.fit(number, Vrc6::MIN_PRG_BANKS)?, false)),
+            26 => Box::new(Vrc6::new(rom.fit(number, Vrc6::MIN_PRG_BANKS)?, true)),
+            66 => Box::new(Gxrom::new(rom.fit(number, Gxrom::MIN_PRG_BANKS)?)),
+            69 => Box::new(Fme7::new(rom.fit(number, Fme7::MIN_PRG_BANKS)?)),
+            _ => return Err(CartridgeError::UnsupportedMapper(number)),
+        };
+
+        Ok(Self {
+            mapper,
+            header: Some(header),
+        })
+    }
+
+    /// A cartridge with a board built outside of this crate.
+    pub fn new(mapper: Box<dyn Mapper>) -> Self {
+        Self {
+            mapper,
+            header: None,
         }
     }
+
+    /// The header of the ROM file the cartridge was loaded from.
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    pub fn chr_read(&mut self, address: u16) -> u8 {
+        self.mapper.chr_read(address)
+    }
+
+    pub fn chr_write(&mut self, address: u16, value: u8) {
+        self.mapper.chr_write(address, value);
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.mirroring()
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.mapper.irq_pending()
+    }
+
+    pub fn expansion_audio(&self) -> Option<ExpansionAudio> {
+        self.mapper.expansion_audio()
+    }
+
+    /// The contents of the battery-backed PRG RAM, for saving games.
+    pub fn save_ram(&self) -> Option<&[u8]> {
+        let battery = self.header.is_some_and(|header| header.battery);
+        let ram = self.mapper.prg_ram();
+        (battery && !ram.is_empty()).then_some(ram)
+    }
+
+    /// Restores battery-backed PRG RAM saved with `save_ram`.
+    pub fn load_save_ram(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        let expected = self.save_ram().map_or(0, <[u8]>::len);
+        if expected == 0 || data.len() != expected {
+            return Err(CartridgeError::SaveRamSize {
+                expected,
+                actual: data.len(),
+            });
+        }
+        self.mapper.prg_ram_mut().copy_from_slice(data);
+        Ok(())
+    }
 }
 
 impl Bus for Cartridge {
-    fn read(&self, address: u16) -> u8 {
-        match address {
-            0x6000..=0x7FFF => {
-                let address = address - 0x6000;
-                self.cartridge_ram[address as usize]
-            }
-            0x8000..=0xFFFF => {
-                let mut address = address - 0x8000;
-                // Roms are usually 1 or 2 banks.
-                // If rom is 16KB, address > 16KB are mirrored
-                if self.prg_rom.len() == 0x4000 && address >= 0x4000 {
-                    address = address % 0x4000;
-                }
-                self.prg_rom[address as usize]
-            }
-            _ => panic!("Access to unmapped cartridge address: {:4X}", address),
-        }
+    fn peek(&self, address: u16) -> u8 {
+        self.mapper.cpu_read(address)
     }
 
     fn write(&mut self, address: u16, value: u8) {
-        match address {
-            0x6000..=0x7FFF => {
-                let address = address - 0x6000;
-                self.cartridge_ram[address as usize] = value;
-            }
-            0x8000..=0xFFFF => {
-                panic!("Can't write to cartridge rom address: {:4X}", address)
-            }
-            _ => panic!("Access to unmapped cartridge address: {:4X}", address),
-        }
+        self.mapper.cpu_write(address, value);
+    }
+
+    fn tick(&mut self) {
+        self.mapper.clock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::Bus;
+
+    use super::{Cartridge, CartridgeError, Mirroring};
+
+    fn header(prg_banks: u8, chr_banks: u8, flags6: u8, flags7: u8) -> Vec<u8> {
+        let mut rom = vec![0u8; 16];
+        rom[0..4].copy_from_slice(b"NES\x1a");
+        rom[4] = prg_banks;
+        rom[5] = chr_banks;
+        rom[6] = flags6;
+        rom[7] = flags7;
+        rom
+    }
+
+    #[test]
+    fn test_nrom() {
+        let mut rom = header(1, 0, 0x01, 0x00);
+        rom.resize(16 + 0x4000, 0);
+        rom[16] = 0x42;
+        let mut cartridge = Cartridge::from_rom(&rom).unwrap();
+
+        assert_eq!(cartridge.mirroring(), Mirroring::Vertical);
+        // 16KB of PRG ROM shows up twice
+        assert_eq!(cartridge.peek(0x8000), 0x42);
+        assert_eq!(cartridge.peek(0xC000), 0x42);
+        cartridge.write(0x8000, 0x00);
+        assert_eq!(cartridge.peek(0x8000), 0x42);
+
+        cartridge.write(0x6000, 0x17);
+        assert_eq!(cartridge.peek(0x6000), 0x17);
+
+        // Without CHR ROM the board has CHR RAM
+        cartridge.chr_write(0x1234, 0x99);
+        assert_eq!(cartridge.chr_read(0x1234), 0x99);
+        assert!(!cartridge.irq_pending());
+    }
+
+    #[test]
+    fn test_chr_rom() {
+        let mut rom = header(1, 2, 0x04, 0x00);
+        rom.resize(16 + 512 + 0x4000, 0);
+        rom.extend((0..0x4000).map(|i| (i >> 8) as u8));
+        let mut cartridge = Cartridge::from_rom(&rom).unwrap();
+
+        // CHR ROM comes right after PRG ROM, and can't be written
+        assert_eq!(cartridge.chr_read(0x0000), 0x00);
+        assert_eq!(cartridge.chr_read(0x1FFF), 0x1F);
+        cartridge.chr_write(0x1FFF, 0x99);
+        assert_eq!(cartridge.chr_read(0x1FFF), 0x1F);
+    }
+
+    #[test]
+    fn test_save_ram() {
+        let mut rom = header(1, 1, 0x02, 0x00);
+        rom.resize(16 + 0x4000 + 0x2000, 0);
+        let mut cartridge = Cartridge::from_rom(&rom).unwrap();
+
+        cartridge.write(0x6000, 0x42);
+        let save = cartridge.save_ram().unwrap().to_vec();
+        assert_eq!(save.len(), 0x2000);
+        assert_eq!(save[0], 0x42);
+
+        let mut cartridge = Cartridge::from_rom(&rom).unwrap();
+        assert_eq!(
+            cartridge.load_save_ram(&save[1..]),
+            Err(CartridgeError::SaveRamSize {
+                expected: 0x2000,
+                actual: 0x1FFF,
+            })
+        );
+        cartridge.load_save_ram(&save).unwrap();
+        assert_eq!(cartridge.peek(0x6000), 0x42);
+
+        // Without a battery there's nothing to save
+        rom[6] = 0x00;
+        let mut cartridge = Cartridge::from_rom(&rom).unwrap();
+        assert_eq!(cartridge.save_ram(), None);
+        assert!(cartridge.load_save_ram(&save).is_err());
+    }
+
+    #[test]
+    fn test_invalid_rom() {
+        let from_rom = |rom: &[u8]| Cartridge::from_rom(rom).err();
+
+        assert_eq!(from_rom(&[]), Some(CartridgeError::InvalidMagic));
+        let mut rom = header(1, 1, 0x00, 0x00);
+        rom[3] = 0x00;
+        assert_eq!(from_rom(&rom), Some(CartridgeError::InvalidMagic));
+
+        assert_eq!(
+            from_rom(&header(0, 1, 0x00, 0x00)),
+            Some(CartridgeError::NoPrgRom)
+        );
+
+        // The trainer and CHR ROM count too
+        let mut rom = header(1, 1, 0x04, 0x00);
+        rom.resize(16 + 0x4000 + 0x2000, 0);
+        assert_eq!(
+            from_rom(&rom),
+            Some(CartridgeError::Truncated {
+                expected: 16 + 512 + 0x4000 + 0x2000,
+                actual: 16 + 0x4000 + 0x2000,
+            })
+        );
+
+        let mut rom = header(1, 0, 0xf0, 0x10);
+        rom.resize(16 + 0x4000, 0);
+        assert_eq!(
+            from_rom(&rom),
+            Some(CartridgeError::UnsupportedMapper(0x1f))
+        );
+
+        // NES 2.0 can describe PRG ROM smaller than a bank: 2^4 * 3 bytes
+        let mut rom = header(0x11, 0, 0x40, 0x08);
+        rom[9] = 0x0f;
+        rom.resize(16 + 48, 0);
+        assert_eq!(
+            from_rom(&rom),
+            Some(CartridgeError::PrgRomSize { mapper: 4, len: 48 })
+        );
+
+        // MMC2 always maps four banks
+        let mut rom = header(1, 0, 0x90, 0x00);
+        rom.resize(16 + 0x4000, 0);
+        assert_eq!(
+            from_rom(&rom),
+            Some(CartridgeError::PrgRomSize {
+                mapper: 9,
+                len: 0x4000,
+            })
+        );
+
+        let mut rom = header(1, 0, 0xf0, 0x10);
+        rom.resize(16 + 0x4000, 0);
+
+        // Garbage at the end of the header hides byte 7
+        rom[7] = b'D';
+        rom[6] = 0x00;
+        rom[12..16].copy_from_slice(b"ude!");
+        assert!(Cartridge::from_rom(&rom).is_ok());
     }
 }
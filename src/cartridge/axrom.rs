@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x8000;
+
+/// Mapper 7: 32KB PRG banks, CHR RAM and a choice of single screen
+/// nametables.
+pub(crate) struct Axrom {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_bank: usize,
+    mirroring: Mirroring,
+}
+
+impl Axrom {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        let prg_banks = rom.prg_rom.len().div_ceil(PRG_BANK_LEN);
+        Self {
+            prg_rom: rom.prg_rom,
+            chr,
+            chr_ram,
+            // Games can't count on a bank at power on, but the last one is
+            // the safest bet
+            prg_bank: prg_banks - 1,
+            mirroring: Mirroring::SingleScreenLower,
+        }
+    }
+}
+
+impl Mapper for Axrom {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x8000..=0xFFFF => {
+                let address = self.prg_bank * PRG_BANK_LEN + (address - 0x8000) as usize;
+                self.prg_rom[address % self.prg_rom.len()]
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x8000..=0xFFFF => {
+                self.prg_bank = (value & 0x07) as usize;
+                self.mirroring = if value & 0x10 != 0 {
+                    Mirroring::SingleScreenUpper
+                } else {
+                    Mirroring::SingleScreenLower
+                };
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[address as usize % self.chr.len()]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let len = self.chr.len();
+            self.chr[address as usize % len] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Axrom, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    #[test]
+    fn test_axrom() {
+        let mut axrom = Axrom::new(RomImage {
+            chr_ram_len: 0x2000,
+            mirroring: Mirroring::Horizontal,
+            ..RomImage::banked(4, PRG_BANK_LEN, 0, 0)
+        });
+
+        assert_eq!(axrom.cpu_read(0x8000), 3);
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenLower);
+
+        axrom.cpu_write(0x8000, 0x11);
+        assert_eq!(axrom.cpu_read(0x8000), 1);
+        assert_eq!(axrom.cpu_read(0xFFFF), 1);
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenUpper);
+
+        // Banks past the end of the ROM wrap around
+        axrom.cpu_write(0x8000, 0x06);
+        assert_eq!(axrom.cpu_read(0x8000), 2);
+        assert_eq!(axrom.mirroring(), Mirroring::SingleScreenLower);
+
+        axrom.chr_write(0x0123, 0x42);
+        assert_eq!(axrom.chr_read(0x0123), 0x42);
+    }
+}
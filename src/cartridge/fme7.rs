@@ -0,0 +1,236 @@
+use alloc::{vec, vec::Vec};
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x2000;
+const CHR_BANK_LEN: usize = 0x0400;
+
+/// Mapper 69: Sunsoft's FME-7 and 5B. Its registers are written through a
+/// command port at $8000 and a parameter port at $A000. It has four
+/// switchable 8KB PRG banks, eight 1KB CHR banks and a 16-bit CPU cycle
+/// IRQ counter.
+pub(crate) struct Fme7 {
+    prg_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    command: u8,
+    chr_banks: [usize; 8],
+    // The banks at $6000, $8000, $A000 and $C000
+    prg_banks: [usize; 4],
+    // $6000 maps RAM instead of ROM
+    prg_ram_selected: bool,
+    prg_ram_enabled: bool,
+    mirroring: Mirroring,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_counter: u16,
+    irq_pending: bool,
+}
+
+impl Fme7 {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            prg_ram: vec![0x00; rom.prg_ram_len],
+            chr,
+            chr_ram,
+            command: 0,
+            chr_banks: [0; 8],
+            prg_banks: [0; 4],
+            prg_ram_selected: false,
+            prg_ram_enabled: false,
+            mirroring: rom.mirroring,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+            irq_pending: false,
+        }
+    }
+
+    fn prg_address(&self, address: u16) -> usize {
+        let bank = match address {
+            0xE000..=0xFFFF => self.prg_rom.len() / PRG_BANK_LEN - 1,
+            _ => self.prg_banks[(address as usize - 0x6000) / PRG_BANK_LEN],
+        };
+        (bank * PRG_BANK_LEN + address as usize % PRG_BANK_LEN) % self.prg_rom.len()
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        let bank = self.chr_banks[address as usize / CHR_BANK_LEN];
+        (bank * CHR_BANK_LEN + address as usize % CHR_BANK_LEN) % self.chr.len()
+    }
+
+    fn write_parameter(&mut self, value: u8) {
+        match self.command {
+            0x0..=0x7 => self.chr_banks[self.command as usize] = value as usize,
+            0x8 => {
+                self.prg_ram_enabled = value & 0x80 != 0;
+                self.prg_ram_selected = value & 0x40 != 0;
+                self.prg_banks[0] = (value & 0x3f) as usize;
+            }
+            0x9..=0xB => self.prg_banks[(self.command - 0x8) as usize] = (value & 0x3f) as usize,
+            0xC => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            0xD => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_counter_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xff00) | u16::from(value),
+            _ => self.irq_counter = (self.irq_counter & 0x00ff) | (u16::from(value) << 8),
+        }
+    }
+}
+
+impl Mapper for Fme7 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_selected => {
+                if self.prg_ram_enabled && !self.prg_ram.is_empty() {
+                    self.prg_ram[(address - 0x6000) as usize % self.prg_ram.len()]
+                } else {
+                    warn!("Access to disabled cartridge ram: {:4X}", address);
+                    0
+                }
+            }
+            0x6000..=0xFFFF => self.prg_rom[self.prg_address(address)],
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x6000..=0x7FFF => {
+                if self.prg_ram_selected && self.prg_ram_enabled && !self.prg_ram.is_empty() {
+                    let len = self.prg_ram.len();
+                    self.prg_ram[(address - 0x6000) as usize % len] = value;
+                }
+            }
+            0x8000..=0x9FFF => self.command = value & 0x0f,
+            0xA000..=0xBFFF => self.write_parameter(value),
+            // TODO: Sunsoft 5B audio
+            0xC000..=0xFFFF => {}
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[self.chr_address(address)]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn clock(&mut self) {
+        if !self.irq_counter_enabled {
+            return;
+        }
+        self.irq_counter = self.irq_counter.wrapping_sub(1);
+        if self.irq_counter == 0xffff && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fme7, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    fn board() -> Fme7 {
+        Fme7::new(RomImage::banked(32, PRG_BANK_LEN, 256, CHR_BANK_LEN))
+    }
+
+    fn command(fme7: &mut Fme7, command: u8, parameter: u8) {
+        fme7.cpu_write(0x8000, command);
+        fme7.cpu_write(0xA000, parameter);
+    }
+
+    #[test]
+    fn test_banks() {
+        let mut fme7 = board();
+        for bank in 0..8 {
+            command(&mut fme7, bank, 0x20 + bank);
+        }
+        command(&mut fme7, 0x8, 0x05);
+        command(&mut fme7, 0x9, 0x06);
+        command(&mut fme7, 0xA, 0x07);
+        command(&mut fme7, 0xB, 0x48);
+
+        assert_eq!(fme7.chr_read(0x0000), 0x20);
+        assert_eq!(fme7.chr_read(0x1FFF), 0x27);
+        assert_eq!(fme7.cpu_read(0x6000), 5);
+        assert_eq!(fme7.cpu_read(0x8000), 6);
+        assert_eq!(fme7.cpu_read(0xA000), 7);
+        assert_eq!(fme7.cpu_read(0xC000), 8);
+        assert_eq!(fme7.cpu_read(0xE000), 31);
+
+        // $6000 can hold RAM instead
+        command(&mut fme7, 0x8, 0xC0);
+        fme7.cpu_write(0x6000, 0x42);
+        assert_eq!(fme7.cpu_read(0x6000), 0x42);
+
+        command(&mut fme7, 0xC, 0x03);
+        assert_eq!(fme7.mirroring(), Mirroring::SingleScreenUpper);
+    }
+
+    #[test]
+    fn test_irq() {
+        let mut fme7 = board();
+        command(&mut fme7, 0xE, 0x02);
+        command(&mut fme7, 0xF, 0x00);
+        command(&mut fme7, 0xD, 0x81);
+
+        // Fires when the counter wraps past 0
+        fme7.clock();
+        fme7.clock();
+        assert!(!fme7.irq_pending());
+        fme7.clock();
+        assert!(fme7.irq_pending());
+
+        // Any write to the control register acknowledges it
+        command(&mut fme7, 0xD, 0x80);
+        assert!(!fme7.irq_pending());
+        for _ in 0..0x10000 {
+            fme7.clock();
+        }
+        assert!(!fme7.irq_pending());
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::vec::Vec;
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x8000;
+const CHR_BANK_LEN: usize = 0x2000;
+
+/// Mapper 66: a 32KB PRG bank and an 8KB CHR bank, both picked by the same
+/// register.
+pub(crate) struct Gxrom {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_bank: usize,
+    chr_bank: usize,
+    mirroring: Mirroring,
+}
+
+impl Gxrom {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            chr,
+            chr_ram,
+            prg_bank: 0,
+            chr_bank: 0,
+            mirroring: rom.mirroring,
+        }
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        (self.chr_bank * CHR_BANK_LEN + address as usize) % self.chr.len()
+    }
+}
+
+impl Mapper for Gxrom {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x8000..=0xFFFF => {
+                let address = self.prg_bank * PRG_BANK_LEN + (address - 0x8000) as usize;
+                self.prg_rom[address % self.prg_rom.len()]
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x8000..=0xFFFF => {
+                self.prg_bank = ((value >> 4) & 0x03) as usize;
+                self.chr_bank = (value & 0x03) as usize;
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[self.chr_address(address)]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gxrom, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    #[test]
+    fn test_gxrom() {
+        let mut gxrom = Gxrom::new(RomImage::banked(2, PRG_BANK_LEN, 4, CHR_BANK_LEN));
+
+        assert_eq!(gxrom.cpu_read(0x8000), 0);
+        assert_eq!(gxrom.chr_read(0x0000), 0);
+
+        gxrom.cpu_write(0x8000, 0x13);
+        assert_eq!(gxrom.cpu_read(0x8000), 1);
+        assert_eq!(gxrom.cpu_read(0xFFFF), 1);
+        assert_eq!(gxrom.chr_read(0x0000), 3);
+        assert_eq!(gxrom.chr_read(0x1FFF), 3);
+
+        // CHR ROM can't be written
+        gxrom.chr_write(0x0000, 0x42);
+        assert_eq!(gxrom.chr_read(0x0000), 3);
+        assert_eq!(gxrom.mirroring(), Mirroring::Vertical);
+    }
+}
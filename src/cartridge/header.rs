@@ -0,0 +1,229 @@
+use log::warn;
+
+use super::{CartridgeError, Mirroring};
+
+pub(crate) const HEADER_LEN: usize = 16;
+pub(crate) const TRAINER_LEN: usize = 512;
+const PRG_BANK_LEN: usize = 0x4000;
+const CHR_BANK_LEN: usize = 0x2000;
+// What iNES 1.0 files can't describe, boards almost always have
+const INES_PRG_RAM_LEN: usize = 0x2000;
+const INES_CHR_RAM_LEN: usize = 0x2000;
+
+/// The CPU/PPU timing a game was made for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    /// Runs on either.
+    MultiRegion,
+    Dendy,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    VsSystem,
+    Playchoice10,
+    /// One of the consoles listed in byte 13 of a NES 2.0 header.
+    Extended(u8),
+}
+
+/// What an iNES or NES 2.0 header says about the cartridge. iNES 1.0
+/// can't describe RAM sizes or timing, so those are the usual guesses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub nes2: bool,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub prg_rom_len: usize,
+    pub chr_rom_len: usize,
+    pub prg_ram_len: usize,
+    /// Battery-backed PRG RAM.
+    pub prg_nvram_len: usize,
+    pub chr_ram_len: usize,
+    pub chr_nvram_len: usize,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    pub trainer: bool,
+    pub timing: Timing,
+    pub console_type: ConsoleType,
+}
+
+impl Header {
+    pub fn parse(buffer: &[u8]) -> Result<Self, CartridgeError> {
+        if buffer.len() < HEADER_LEN || &buffer[0..4] != b"NES\x1a" {
+            return Err(CartridgeError::InvalidMagic);
+        }
+
+        let flags6 = buffer[6];
+        let nes2 = buffer[7] & 0x0c == 0x08;
+        // Old dumping tools left text like "DiskDude!" in bytes 7-15, which
+        // would make up a nonsense upper mapper nibble
+        let flags7 = if !nes2 && buffer[12..16].iter().any(|&byte| byte != 0) {
+            warn!("Garbage in the iNES header, ignoring byte 7");
+            0
+        } else {
+            buffer[7]
+        };
+
+        let mirroring = if flags6 & 0b1000 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0b1 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+        let battery = flags6 & 0b10 != 0;
+        let mapper = u16::from((flags7 & 0xf0) | (flags6 >> 4));
+        let console_type = match flags7 & 0x03 {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem,
+            2 => ConsoleType::Playchoice10,
+            _ => ConsoleType::Extended(if nes2 { buffer[13] & 0x0f } else { 0 }),
+        };
+
+        let header = if nes2 {
+            Self {
+                nes2,
+                mapper: mapper | (u16::from(buffer[8] & 0x0f) << 8),
+                submapper: buffer[8] >> 4,
+                prg_rom_len: rom_len(buffer[4], buffer[9] & 0x0f, PRG_BANK_LEN),
+                chr_rom_len: rom_len(buffer[5], buffer[9] >> 4, CHR_BANK_LEN),
+                prg_ram_len: ram_len(buffer[10] & 0x0f),
+                prg_nvram_len: ram_len(buffer[10] >> 4),
+                chr_ram_len: ram_len(buffer[11] & 0x0f),
+                chr_nvram_len: ram_len(buffer[11] >> 4),
+                mirroring,
+                battery,
+                trainer: flags6 & 0b100 != 0,
+                timing: match buffer[12] & 0x03 {
+                    0 => Timing::Ntsc,
+                    1 => Timing::Pal,
+                    2 => Timing::MultiRegion,
+                    _ => Timing::Dendy,
+                },
+                console_type,
+            }
+        } else {
+            let chr_rom_len = buffer[5] as usize * CHR_BANK_LEN;
+            Self {
+                nes2,
+                mapper,
+                submapper: 0,
+                prg_rom_len: buffer[4] as usize * PRG_BANK_LEN,
+                chr_rom_len,
+                prg_ram_len: if battery { 0 } else { INES_PRG_RAM_LEN },
+                prg_nvram_len: if battery { INES_PRG_RAM_LEN } else { 0 },
+                chr_ram_len: if chr_rom_len == 0 {
+                    INES_CHR_RAM_LEN
+                } else {
+                    0
+                },
+                chr_nvram_len: 0,
+                mirroring,
+                battery,
+                trainer: flags6 & 0b100 != 0,
+                timing: Timing::Ntsc,
+                console_type,
+            }
+        };
+
+        if header.prg_rom_len == 0 {
+            return Err(CartridgeError::NoPrgRom);
+        }
+        Ok(header)
+    }
+
+    /// The size of the file the header describes.
+    pub fn file_len(&self) -> usize {
+        let trainer = if self.trainer { TRAINER_LEN } else { 0 };
+        HEADER_LEN
+            .saturating_add(trainer)
+            .saturating_add(self.prg_rom_len)
+            .saturating_add(self.chr_rom_len)
+    }
+}
+
+// NES 2.0 sizes are a count of banks with the MSB nibble in byte 9, or
+// 2^E * (MM * 2 + 1) bytes when that nibble is $F
+fn rom_len(lsb: u8, msb: u8, bank_len: usize) -> usize {
+    if msb == 0x0f {
+        let exponent = u32::from(lsb >> 2);
+        let multiplier = usize::from(lsb & 0x03) * 2 + 1;
+        1usize
+            .checked_shl(exponent)
+            .map_or(usize::MAX, |len| len.saturating_mul(multiplier))
+    } else {
+        ((usize::from(msb) << 8) | usize::from(lsb)) * bank_len
+    }
+}
+
+// RAM sizes are 64 << shift bytes, or none for a shift of 0
+fn ram_len(shift: u8) -> usize {
+    if shift == 0 {
+        0
+    } else {
+        64 << shift
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConsoleType, Header, Timing};
+    use crate::cartridge::Mirroring;
+
+    fn rom(bytes: [u8; 12]) -> [u8; 16] {
+        let mut header = [0u8; 16];
+        header[0..4].copy_from_slice(b"NES\x1a");
+        header[4..].copy_from_slice(&bytes);
+        header
+    }
+
+    #[test]
+    fn test_ines() {
+        let header = Header::parse(&rom([2, 0, 0x12, 0x40, 0, 0, 0, 0, 0, 0, 0, 0])).unwrap();
+
+        assert!(!header.nes2);
+        assert_eq!(header.mapper, 0x41);
+        assert_eq!(header.prg_rom_len, 0x8000);
+        assert_eq!(header.chr_rom_len, 0);
+        assert_eq!(header.mirroring, Mirroring::Horizontal);
+        // iNES 1.0 guesses at the RAM
+        assert!(header.battery);
+        assert_eq!(header.prg_ram_len, 0);
+        assert_eq!(header.prg_nvram_len, 0x2000);
+        assert_eq!(header.chr_ram_len, 0x2000);
+        assert_eq!(header.timing, Timing::Ntsc);
+        assert_eq!(header.console_type, ConsoleType::Nes);
+        assert_eq!(header.file_len(), 16 + 0x8000);
+    }
+
+    #[test]
+    fn test_nes2() {
+        let header = Header::parse(&rom([
+            0x02, 0x01, 0x41, 0x49, 0x31, 0x10, 0x07, 0x70, 0x01, 0x03, 0, 0,
+        ]))
+        .unwrap();
+
+        assert!(header.nes2);
+        assert_eq!(header.mapper, 0x144);
+        assert_eq!(header.submapper, 3);
+        assert_eq!(header.prg_rom_len, 0x8000);
+        assert_eq!(header.chr_rom_len, 0x101 * 0x2000);
+        assert_eq!(header.prg_ram_len, 0x2000);
+        assert_eq!(header.prg_nvram_len, 0);
+        assert_eq!(header.chr_ram_len, 0);
+        assert_eq!(header.chr_nvram_len, 0x2000);
+        assert_eq!(header.mirroring, Mirroring::Vertical);
+        assert_eq!(header.timing, Timing::Pal);
+        assert_eq!(header.console_type, ConsoleType::VsSystem);
+
+        // Exponent-multiplier sizes: 2^4 * 3 bytes
+        let header =
+            Header::parse(&rom([0x11, 0, 0, 0x08, 0, 0x0f, 0, 0, 0x03, 0x04, 0, 0])).unwrap();
+        assert_eq!(header.prg_rom_len, 48);
+        assert_eq!(header.timing, Timing::Dendy);
+        assert_eq!(header.console_type, ConsoleType::Nes);
+    }
+}
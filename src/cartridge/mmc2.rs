@@ -0,0 +1,168 @@
+use alloc::vec::Vec;
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x2000;
+const CHR_BANK_LEN: usize = 0x1000;
+
+/// Mapper 9: one switchable 8KB PRG bank, and two 4KB CHR windows that each
+/// pick between two banks depending on the last tile the PPU fetched.
+pub(crate) struct Mmc2 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_bank: usize,
+    // The $FD and $FE banks of each pattern table
+    chr_banks: [[usize; 2]; 2],
+    // Whether each pattern table last saw tile $FE rather than $FD
+    latches: [bool; 2],
+    mirroring: Mirroring,
+}
+
+impl Mmc2 {
+    // A switchable bank and the three fixed ones above it
+    pub(crate) const MIN_PRG_BANKS: usize = 4;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            chr,
+            chr_ram,
+            prg_bank: 0,
+            chr_banks: [[0; 2]; 2],
+            latches: [true; 2],
+            mirroring: rom.mirroring,
+        }
+    }
+
+    fn prg_address(&self, address: u16) -> usize {
+        let banks = self.prg_rom.len() / PRG_BANK_LEN;
+        // The last three banks are fixed at $A000-$FFFF
+        let bank = match address {
+            0x8000..=0x9FFF => self.prg_bank,
+            _ => banks + (address as usize - 0x8000) / PRG_BANK_LEN - 4,
+        };
+        (bank * PRG_BANK_LEN + address as usize % PRG_BANK_LEN) % self.prg_rom.len()
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        let table = (address >> 12) as usize & 0x01;
+        let bank = self.chr_banks[table][self.latches[table] as usize];
+        (bank * CHR_BANK_LEN + address as usize % CHR_BANK_LEN) % self.chr.len()
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x8000..=0xFFFF => self.prg_rom[self.prg_address(address)],
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        let bank = (value & 0x1f) as usize;
+        match address {
+            0xA000..=0xAFFF => self.prg_bank = (value & 0x0f) as usize,
+            0xB000..=0xBFFF => self.chr_banks[0][0] = bank,
+            0xC000..=0xCFFF => self.chr_banks[0][1] = bank,
+            0xD000..=0xDFFF => self.chr_banks[1][0] = bank,
+            0xE000..=0xEFFF => self.chr_banks[1][1] = bank,
+            0xF000..=0xFFFF => {
+                self.mirroring = if value & 0x01 != 0 {
+                    Mirroring::Horizontal
+                } else {
+                    Mirroring::Vertical
+                };
+            }
+            _ => {
+                warn!("Write to cartridge rom address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        let value = self.chr[self.chr_address(address)];
+        // The latches flip after the fetch, so the tile itself still comes
+        // from the old bank
+        match address {
+            0x0FD8 => self.latches[0] = false,
+            0x0FE8 => self.latches[0] = true,
+            0x1FD8..=0x1FDF => self.latches[1] = false,
+            0x1FE8..=0x1FEF => self.latches[1] = true,
+            _ => {}
+        }
+        value
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mmc2, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    #[test]
+    fn test_mmc2() {
+        let mut mmc2 = Mmc2::new(RomImage::banked(16, PRG_BANK_LEN, 32, CHR_BANK_LEN));
+
+        mmc2.cpu_write(0xA000, 0x05);
+        assert_eq!(mmc2.cpu_read(0x8000), 5);
+        assert_eq!(mmc2.cpu_read(0xA000), 13);
+        assert_eq!(mmc2.cpu_read(0xC000), 14);
+        assert_eq!(mmc2.cpu_read(0xE000), 15);
+
+        mmc2.cpu_write(0xB000, 1);
+        mmc2.cpu_write(0xC000, 2);
+        mmc2.cpu_write(0xD000, 3);
+        mmc2.cpu_write(0xE000, 4);
+        assert_eq!(mmc2.chr_read(0x0000), 2);
+        assert_eq!(mmc2.chr_read(0x1000), 4);
+
+        // Fetching tile $FD switches the bank for what comes after
+        assert_eq!(mmc2.chr_read(0x0FD8), 2);
+        assert_eq!(mmc2.chr_read(0x0000), 1);
+        assert_eq!(mmc2.chr_read(0x1000), 4);
+        assert_eq!(mmc2.chr_read(0x1FDB), 4);
+        assert_eq!(mmc2.chr_read(0x1000), 3);
+        // Only the first byte of tile $FD/$FE counts for the lower table
+        mmc2.chr_read(0x0FE9);
+        assert_eq!(mmc2.chr_read(0x0000), 1);
+        mmc2.chr_read(0x0FE8);
+        assert_eq!(mmc2.chr_read(0x0000), 2);
+
+        mmc2.cpu_write(0xF000, 0x01);
+        assert_eq!(mmc2.mirroring(), Mirroring::Horizontal);
+    }
+
+    #[test]
+    fn test_chr_ram() {
+        let mut mmc2 = Mmc2::new(RomImage::banked(16, PRG_BANK_LEN, 32, CHR_BANK_LEN));
+        // CHR ROM can't be written
+        mmc2.chr_write(0x0000, 0x42);
+        assert_eq!(mmc2.chr_read(0x0000), 0);
+
+        let mut mmc2 = Mmc2::new(RomImage {
+            chr_ram_len: 0x2000,
+            ..RomImage::banked(16, PRG_BANK_LEN, 0, 0)
+        });
+        mmc2.chr_write(0x1123, 0x42);
+        assert_eq!(mmc2.chr_read(0x1123), 0x42);
+    }
+}
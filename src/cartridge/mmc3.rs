@@ -0,0 +1,295 @@
+use alloc::{vec, vec::Vec};
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x2000;
+const CHR_BANK_LEN: usize = 0x0400;
+
+/// Mapper 4: 8KB PRG banks, 1KB and 2KB CHR banks, and an IRQ counter
+/// clocked by the PPU's A12 line, once per scanline on most games.
+pub(crate) struct Mmc3 {
+    prg_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    // Which of the bank registers the next $8001 write goes to, plus the
+    // PRG and CHR layout bits
+    bank_select: u8,
+    banks: [u8; 8],
+    mirroring: Mirroring,
+    prg_ram_enabled: bool,
+    prg_ram_write_protect: bool,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+    a12: bool,
+}
+
+impl Mmc3 {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            prg_ram: vec![0x00; rom.prg_ram_len],
+            chr,
+            chr_ram,
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring: rom.mirroring,
+            prg_ram_enabled: true,
+            prg_ram_write_protect: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+            a12: false,
+        }
+    }
+
+    fn prg_address(&self, address: u16) -> usize {
+        let banks = self.prg_rom.len() / PRG_BANK_LEN;
+        let second_last = banks.saturating_sub(2);
+        let slot = (address as usize - 0x8000) / PRG_BANK_LEN;
+        let bank = match (slot, self.bank_select & 0x40 != 0) {
+            (0, false) | (2, true) => self.banks[6] as usize,
+            (1, _) => self.banks[7] as usize,
+            (0, true) | (2, false) => second_last,
+            _ => banks - 1,
+        };
+        (bank % banks) * PRG_BANK_LEN + (address as usize % PRG_BANK_LEN)
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        // The CHR inversion bit swaps the 2KB and 1KB halves
+        let address = if self.bank_select & 0x80 != 0 {
+            address ^ 0x1000
+        } else {
+            address
+        } as usize;
+        let bank = match address / CHR_BANK_LEN {
+            0 => self.banks[0] & 0xfe,
+            1 => self.banks[0] | 0x01,
+            2 => self.banks[1] & 0xfe,
+            3 => self.banks[1] | 0x01,
+            slot => self.banks[slot - 2],
+        } as usize;
+        (bank * CHR_BANK_LEN + address % CHR_BANK_LEN) % self.chr.len()
+    }
+
+    // The PPU fetches background and sprite patterns from different tables,
+    // so A12 rises once per scanline when they differ
+    fn watch_a12(&mut self, address: u16) {
+        let a12 = address & 0x1000 != 0;
+        if a12 && !self.a12 {
+            self.clock_irq();
+        }
+        self.a12 = a12;
+    }
+
+    fn clock_irq(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled && !self.prg_ram.is_empty() => {
+                self.prg_ram[(address - 0x6000) as usize % self.prg_ram.len()]
+            }
+            0x8000..=0xFFFF => self.prg_rom[self.prg_address(address)],
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        match (address, address & 0x01 == 0) {
+            (0x6000..=0x7FFF, _) => {
+                if self.prg_ram_enabled && !self.prg_ram_write_protect && !self.prg_ram.is_empty() {
+                    let len = self.prg_ram.len();
+                    self.prg_ram[(address - 0x6000) as usize % len] = value;
+                }
+            }
+            (0x8000..=0x9FFF, true) => self.bank_select = value,
+            (0x8000..=0x9FFF, false) => {
+                let register = (self.bank_select & 0x07) as usize;
+                self.banks[register] = value;
+            }
+            (0xA000..=0xBFFF, true) => {
+                // Four screen boards wire their own nametable RAM
+                if self.mirroring != Mirroring::FourScreen {
+                    self.mirroring = if value & 0x01 != 0 {
+                        Mirroring::Horizontal
+                    } else {
+                        Mirroring::Vertical
+                    };
+                }
+            }
+            (0xA000..=0xBFFF, false) => {
+                self.prg_ram_enabled = value & 0x80 != 0;
+                self.prg_ram_write_protect = value & 0x40 != 0;
+            }
+            (0xC000..=0xDFFF, true) => self.irq_latch = value,
+            (0xC000..=0xDFFF, false) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (0xE000..=0xFFFF, true) => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            (0xE000..=0xFFFF, false) => self.irq_enabled = true,
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.watch_a12(address);
+        self.chr[self.chr_address(address)]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        self.watch_a12(address);
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mmc3, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    fn mmc3() -> Mmc3 {
+        Mmc3::new(RomImage::banked(8, PRG_BANK_LEN, 16, CHR_BANK_LEN))
+    }
+
+    #[test]
+    fn test_prg_banks() {
+        let mut mmc3 = mmc3();
+        mmc3.cpu_write(0x8000, 0x06);
+        mmc3.cpu_write(0x8001, 0x02);
+        mmc3.cpu_write(0x8000, 0x07);
+        mmc3.cpu_write(0x8001, 0x03);
+
+        assert_eq!(mmc3.cpu_read(0x8000), 2);
+        assert_eq!(mmc3.cpu_read(0xA000), 3);
+        assert_eq!(mmc3.cpu_read(0xC000), 6);
+        assert_eq!(mmc3.cpu_read(0xE000), 7);
+
+        // PRG mode 1 swaps $8000 and $C000
+        mmc3.cpu_write(0x8000, 0x40);
+        assert_eq!(mmc3.cpu_read(0x8000), 6);
+        assert_eq!(mmc3.cpu_read(0xC000), 2);
+        assert_eq!(mmc3.cpu_read(0xE000), 7);
+    }
+
+    #[test]
+    fn test_chr_banks() {
+        let mut mmc3 = mmc3();
+        for (register, bank) in [(0, 4), (1, 9), (2, 1), (3, 2), (4, 3), (5, 15)] {
+            mmc3.cpu_write(0x8000, register);
+            mmc3.cpu_write(0x8001, bank);
+        }
+
+        // 2KB banks ignore the low bit
+        assert_eq!(mmc3.chr_read(0x0000), 4);
+        assert_eq!(mmc3.chr_read(0x0400), 5);
+        assert_eq!(mmc3.chr_read(0x0800), 8);
+        assert_eq!(mmc3.chr_read(0x0C00), 9);
+        assert_eq!(mmc3.chr_read(0x1000), 1);
+        assert_eq!(mmc3.chr_read(0x1C00), 15);
+
+        mmc3.cpu_write(0x8000, 0x80);
+        assert_eq!(mmc3.chr_read(0x0000), 1);
+        assert_eq!(mmc3.chr_read(0x1000), 4);
+        assert_eq!(mmc3.chr_read(0x1800), 8);
+    }
+
+    #[test]
+    fn test_mirroring_and_prg_ram() {
+        let mut mmc3 = mmc3();
+        mmc3.cpu_write(0xA000, 0x01);
+        assert_eq!(mmc3.mirroring(), Mirroring::Horizontal);
+        mmc3.cpu_write(0xA000, 0x00);
+        assert_eq!(mmc3.mirroring(), Mirroring::Vertical);
+
+        mmc3.cpu_write(0x6000, 0x42);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x42);
+        mmc3.cpu_write(0xA001, 0xC0);
+        mmc3.cpu_write(0x6000, 0x17);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x42);
+        mmc3.cpu_write(0xA001, 0x00);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x00);
+    }
+
+    #[test]
+    fn test_scanline_irq() {
+        let mut mmc3 = mmc3();
+        let scanline = |mmc3: &mut Mmc3| {
+            mmc3.chr_read(0x0000);
+            mmc3.chr_read(0x1000);
+        };
+        mmc3.cpu_write(0xC000, 2);
+        mmc3.cpu_write(0xC001, 0);
+        mmc3.cpu_write(0xE001, 0);
+
+        // The first clock reloads the counter
+        scanline(&mut mmc3);
+        scanline(&mut mmc3);
+        assert!(!mmc3.irq_pending());
+        scanline(&mut mmc3);
+        assert!(mmc3.irq_pending());
+
+        // Staying high doesn't clock it
+        mmc3.cpu_write(0xE000, 0);
+        assert!(!mmc3.irq_pending());
+        mmc3.cpu_write(0xE001, 0);
+        mmc3.chr_read(0x1000);
+        mmc3.chr_read(0x1400);
+        assert!(!mmc3.irq_pending());
+        scanline(&mut mmc3);
+        scanline(&mut mmc3);
+        assert!(!mmc3.irq_pending());
+        scanline(&mut mmc3);
+        assert!(mmc3.irq_pending());
+    }
+}
@@ -0,0 +1,89 @@
+use alloc::{vec, vec::Vec};
+
+use log::warn;
+
+use super::{Mapper, Mirroring, RomImage};
+
+/// Mapper 0: up to 32KB of PRG ROM and 8KB of CHR, with no bank switching.
+pub(crate) struct Nrom {
+    prg_rom: Vec<u8>,
+    // Only Family Basic carts have it
+    prg_ram: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            prg_ram: vec![0x00; rom.prg_ram_len],
+            chr,
+            chr_ram,
+            mirroring: rom.mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => {
+                let address = (address - 0x6000) as usize % self.prg_ram.len();
+                self.prg_ram[address]
+            }
+            0x8000..=0xFFFF => {
+                // Roms are usually 1 or 2 banks.
+                // If rom is 16KB, address > 16KB are mirrored
+                let address = (address - 0x8000) as usize % self.prg_rom.len();
+                self.prg_rom[address]
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => {
+                let address = (address - 0x6000) as usize % self.prg_ram.len();
+                self.prg_ram[address] = value;
+            }
+            0x8000..=0xFFFF => {
+                warn!("Write to cartridge rom address: {:4X}", address);
+            }
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[address as usize % self.chr.len()]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let len = self.chr.len();
+            self.chr[address as usize % len] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+}
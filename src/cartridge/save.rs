@@ -0,0 +1,92 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::Cartridge;
+
+/// Where the save RAM of the ROM at `rom_path` is kept: next to it, with a
+/// .sav extension.
+pub fn sav_path(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("sav")
+}
+
+impl Cartridge {
+    /// Restores save RAM from the .sav file next to the ROM, for calling on
+    /// start. Returns whether there was a save to load.
+    pub fn load_sav(&mut self, rom_path: &Path) -> io::Result<bool> {
+        if self.save_ram().is_none() {
+            return Ok(false);
+        }
+        let data = match fs::read(sav_path(rom_path)) {
+            Ok(data) => data,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        self.load_save_ram(&data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(true)
+    }
+
+    /// Writes save RAM to the .sav file next to the ROM, for calling on
+    /// exit. Returns whether the cartridge had anything to save.
+    pub fn write_sav(&self, rom_path: &Path) -> io::Result<bool> {
+        let Some(data) = self.save_ram() else {
+            return Ok(false);
+        };
+        // Write the whole file before replacing the old save, so a crash
+        // halfway through doesn't lose it
+        let path = sav_path(rom_path);
+        let temp = path.with_extension("sav.tmp");
+        fs::write(&temp, data)?;
+        fs::rename(temp, path)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io, path::Path};
+
+    use super::sav_path;
+    use crate::{bus::Bus, cartridge::Cartridge};
+
+    fn battery_rom() -> Vec<u8> {
+        let mut rom = vec![0u8; 16 + 0x4000];
+        rom[0..4].copy_from_slice(b"NES\x1a");
+        rom[4] = 1;
+        rom[6] = 0x02;
+        rom
+    }
+
+    #[test]
+    fn test_sav_path() {
+        assert_eq!(
+            sav_path(Path::new("roms/zelda.nes")),
+            Path::new("roms/zelda.sav")
+        );
+    }
+
+    #[test]
+    fn test_sav_round_trip() -> io::Result<()> {
+        let dir = env::temp_dir().join(format!("nessie-save-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let rom_path = dir.join("game.nes");
+
+        let mut cartridge = Cartridge::from_rom(&battery_rom()).unwrap();
+        assert!(!cartridge.load_sav(&rom_path)?);
+        cartridge.write(0x7FFF, 0x42);
+        assert!(cartridge.write_sav(&rom_path)?);
+
+        let mut cartridge = Cartridge::from_rom(&battery_rom()).unwrap();
+        assert!(cartridge.load_sav(&rom_path)?);
+        assert_eq!(cartridge.peek(0x7FFF), 0x42);
+
+        // A save of the wrong size is rejected
+        fs::write(sav_path(&rom_path), [0u8; 16])?;
+        let error = cartridge.load_sav(&rom_path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(dir)
+    }
+}
@@ -0,0 +1,305 @@
+use alloc::{vec, vec::Vec};
+
+use log::warn;
+
+use super::{vrc_irq::VrcIrq, Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x2000;
+const CHR_BANK_LEN: usize = 0x0400;
+
+/// How a VRC2 or VRC4 board is wired: which CPU address lines pick the
+/// registers within each $1000 range, and which chip it carries.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Vrc4Variant {
+    // The address bits read as the register's bit 0 and bit 1
+    a0: u16,
+    a1: u16,
+    vrc2: bool,
+    // VRC2a drops the low bit of every CHR bank
+    chr_shift: u8,
+}
+
+impl Vrc4Variant {
+    /// Picks the wiring from the mapper and submapper. Without a submapper
+    /// the lines of every board sharing the mapper number are combined,
+    /// since games only ever write to one of them.
+    pub(crate) fn new(mapper: u16, submapper: u8) -> Self {
+        let (a0, a1, vrc2) = match (mapper, submapper) {
+            (21, 1) => (0x02, 0x04, false),
+            (21, 2) => (0x40, 0x80, false),
+            (21, _) => (0x42, 0x84, false),
+            (22, _) => (0x02, 0x01, true),
+            (23, 1) => (0x01, 0x02, false),
+            (23, 2) => (0x04, 0x08, false),
+            (23, 3) => (0x01, 0x02, true),
+            (23, _) => (0x05, 0x0a, false),
+            (25, 1) => (0x02, 0x01, false),
+            (25, 2) => (0x08, 0x04, false),
+            (25, 3) => (0x02, 0x01, true),
+            (_, _) => (0x0a, 0x05, false),
+        };
+        Self {
+            a0,
+            a1,
+            vrc2,
+            chr_shift: if mapper == 22 { 1 } else { 0 },
+        }
+    }
+}
+
+/// Mappers 21, 22, 23 and 25: Konami's VRC2 and VRC4, with two switchable
+/// 8KB PRG banks and eight 1KB CHR banks. The VRC4 adds a PRG layout
+/// switch, single screen mirroring and an IRQ counter.
+pub(crate) struct Vrc4 {
+    variant: Vrc4Variant,
+    prg_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_banks: [usize; 2],
+    // Swaps $8000 and $C000
+    prg_swap: bool,
+    prg_ram_enabled: bool,
+    chr_banks: [usize; 8],
+    mirroring: Mirroring,
+    // Boards without PRG RAM still answer $6000 with a one bit latch
+    vrc2_latch: u8,
+    irq: VrcIrq,
+}
+
+impl Vrc4 {
+    // $C000-$FFFF holds the last two banks
+    pub(crate) const MIN_PRG_BANKS: usize = 2;
+
+    pub(crate) fn new(mut rom: RomImage, variant: Vrc4Variant) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            variant,
+            prg_rom: rom.prg_rom,
+            prg_ram: vec![0x00; rom.prg_ram_len],
+            chr,
+            chr_ram,
+            prg_banks: [0, 1],
+            prg_swap: false,
+            // The VRC2 has no enable bit
+            prg_ram_enabled: variant.vrc2,
+            chr_banks: [0; 8],
+            mirroring: rom.mirroring,
+            vrc2_latch: 0,
+            irq: VrcIrq::default(),
+        }
+    }
+
+    // Folds the board's address lines into $x000-$x003
+    fn register(&self, address: u16) -> u16 {
+        let a0 = u16::from(address & self.variant.a0 != 0);
+        let a1 = u16::from(address & self.variant.a1 != 0);
+        (address & 0xf000) | (a1 << 1) | a0
+    }
+
+    fn prg_address(&self, address: u16) -> usize {
+        let banks = self.prg_rom.len() / PRG_BANK_LEN;
+        let bank = match ((address - 0x8000) / 0x2000, self.prg_swap) {
+            (0, false) | (2, true) => self.prg_banks[0],
+            (1, _) => self.prg_banks[1],
+            (0, true) | (2, false) => banks - 2,
+            _ => banks - 1,
+        };
+        (bank * PRG_BANK_LEN + address as usize % PRG_BANK_LEN) % self.prg_rom.len()
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        let bank = self.chr_banks[address as usize / CHR_BANK_LEN] >> self.variant.chr_shift;
+        (bank * CHR_BANK_LEN + address as usize % CHR_BANK_LEN) % self.chr.len()
+    }
+}
+
+impl Mapper for Vrc4 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled && !self.prg_ram.is_empty() => {
+                self.prg_ram[(address - 0x6000) as usize % self.prg_ram.len()]
+            }
+            0x6000..=0x6FFF if self.variant.vrc2 => self.vrc2_latch,
+            0x8000..=0xFFFF => self.prg_rom[self.prg_address(address)],
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        if (0x6000..=0x7FFF).contains(&address) {
+            if self.prg_ram_enabled && !self.prg_ram.is_empty() {
+                let len = self.prg_ram.len();
+                self.prg_ram[(address - 0x6000) as usize % len] = value;
+            } else if self.variant.vrc2 && address < 0x7000 {
+                self.vrc2_latch = value & 0x01;
+            }
+            return;
+        }
+
+        let vrc2 = self.variant.vrc2;
+        match self.register(address) {
+            0x8000..=0x8003 => self.prg_banks[0] = (value & 0x1f) as usize,
+            // The VRC2 decodes all four addresses
+            0x9000..=0x9003 if vrc2 => {
+                self.mirroring = if value & 0x01 != 0 {
+                    Mirroring::Horizontal
+                } else {
+                    Mirroring::Vertical
+                };
+            }
+            0x9000..=0x9001 => {
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            0x9002..=0x9003 => {
+                self.prg_ram_enabled = value & 0x01 != 0;
+                self.prg_swap = value & 0x02 != 0;
+            }
+            0xA000..=0xA003 => self.prg_banks[1] = (value & 0x1f) as usize,
+            register @ 0xB000..=0xEFFF => {
+                // Each bank number is split over a low and a high register
+                let bank = ((register - 0xB000) >> 11) as usize | ((register & 0x02) >> 1) as usize;
+                let old = self.chr_banks[bank];
+                self.chr_banks[bank] = if register & 0x01 == 0 {
+                    (old & !0x0f) | (value & 0x0f) as usize
+                } else {
+                    (old & 0x0f) | ((value & 0x1f) as usize) << 4
+                };
+            }
+            0xF000 if !vrc2 => self.irq.write_latch_low(value),
+            0xF001 if !vrc2 => self.irq.write_latch_high(value),
+            0xF002 if !vrc2 => self.irq.write_control(value),
+            0xF003 if !vrc2 => self.irq.acknowledge(),
+            _ => {
+                warn!("Write to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[self.chr_address(address)]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq.pending()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Vrc4, Vrc4Variant, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{Mapper, Mirroring, RomImage};
+
+    fn board(mapper: u16, submapper: u8) -> Vrc4 {
+        Vrc4::new(
+            RomImage::banked(16, PRG_BANK_LEN, 256, CHR_BANK_LEN),
+            Vrc4Variant::new(mapper, submapper),
+        )
+    }
+
+    #[test]
+    fn test_prg_banks() {
+        let mut vrc4 = board(21, 0);
+        vrc4.cpu_write(0x8000, 0x03);
+        vrc4.cpu_write(0xA000, 0x04);
+        assert_eq!(vrc4.cpu_read(0x8000), 3);
+        assert_eq!(vrc4.cpu_read(0xA000), 4);
+        assert_eq!(vrc4.cpu_read(0xC000), 14);
+        assert_eq!(vrc4.cpu_read(0xE000), 15);
+
+        // VRC4c puts the swap mode on A7
+        vrc4.cpu_write(0x9080, 0x02);
+        assert_eq!(vrc4.cpu_read(0x8000), 14);
+        assert_eq!(vrc4.cpu_read(0xC000), 3);
+
+        // RAM needs to be enabled on the VRC4
+        vrc4.cpu_write(0x6000, 0x42);
+        assert_eq!(vrc4.cpu_read(0x6000), 0x00);
+        vrc4.cpu_write(0x9004, 0x01);
+        vrc4.cpu_write(0x6000, 0x42);
+        assert_eq!(vrc4.cpu_read(0x6000), 0x42);
+    }
+
+    #[test]
+    fn test_chr_banks() {
+        // VRC4e and VRC4f disagree on the lines, but combined they agree
+        for (mapper, a0, a1) in [(23, 0x04, 0x08), (23, 0x01, 0x02), (25, 0x02, 0x01)] {
+            let mut vrc4 = board(mapper, 0);
+            vrc4.cpu_write(0xB000, 0x05);
+            vrc4.cpu_write(0xB000 | a0, 0x01);
+            vrc4.cpu_write(0xB000 | a1, 0x07);
+            vrc4.cpu_write(0xE000 | a0 | a1, 0x0f);
+            assert_eq!(vrc4.chr_read(0x0000), 0x15);
+            assert_eq!(vrc4.chr_read(0x0400), 0x07);
+            assert_eq!(vrc4.chr_read(0x1C00), 0xf0);
+        }
+
+        // VRC2a ignores the low bit
+        let mut vrc2 = board(22, 0);
+        vrc2.cpu_write(0xC000, 0x05);
+        assert_eq!(vrc2.chr_read(0x0800), 0x02);
+    }
+
+    #[test]
+    fn test_mirroring() {
+        let mut vrc4 = board(25, 0);
+        vrc4.cpu_write(0x9000, 0x03);
+        assert_eq!(vrc4.mirroring(), Mirroring::SingleScreenUpper);
+
+        // The VRC2 only has the low bit
+        let mut vrc2 = board(23, 3);
+        vrc2.cpu_write(0x9000, 0x03);
+        assert_eq!(vrc2.mirroring(), Mirroring::Horizontal);
+        vrc2.cpu_write(0x9002, 0x00);
+        assert_eq!(vrc2.mirroring(), Mirroring::Vertical);
+    }
+
+    #[test]
+    fn test_irq() {
+        let mut vrc4 = board(23, 1);
+        vrc4.cpu_write(0xF000, 0x0f);
+        vrc4.cpu_write(0xF001, 0x0f);
+        vrc4.cpu_write(0xF002, 0x06);
+        vrc4.clock();
+        assert!(vrc4.irq_pending());
+        vrc4.cpu_write(0xF003, 0x00);
+        assert!(!vrc4.irq_pending());
+
+        // The VRC2 has no counter
+        let mut vrc2 = board(23, 3);
+        vrc2.cpu_write(0xF002, 0x06);
+        vrc2.clock();
+        assert!(!vrc2.irq_pending());
+    }
+}
@@ -0,0 +1,260 @@
+use alloc::{vec, vec::Vec};
+
+use log::warn;
+
+use super::{vrc_irq::VrcIrq, ExpansionAudio, Mapper, Mirroring, RomImage};
+
+const PRG_BANK_LEN: usize = 0x2000;
+const CHR_BANK_LEN: usize = 0x0400;
+
+/// Mappers 24 and 26: Konami's VRC6, with a 16KB and an 8KB switchable PRG
+/// bank, 1KB CHR banks and the VRC IRQ counter. Its sound channels live in
+/// the APU.
+pub(crate) struct Vrc6 {
+    prg_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    // Mapper 26 boards swap A0 and A1
+    swapped_lines: bool,
+    prg_16k_bank: usize,
+    prg_8k_bank: usize,
+    chr_banks: [usize; 8],
+    // $B003: CHR layout, mirroring and PRG RAM enable
+    banking_mode: u8,
+    mirroring: Mirroring,
+    irq: VrcIrq,
+}
+
+impl Vrc6 {
+    pub(crate) const MIN_PRG_BANKS: usize = 1;
+
+    pub(crate) fn new(mut rom: RomImage, swapped_lines: bool) -> Self {
+        let (chr, chr_ram) = rom.take_chr();
+        Self {
+            prg_rom: rom.prg_rom,
+            prg_ram: vec![0x00; rom.prg_ram_len],
+            chr,
+            chr_ram,
+            swapped_lines,
+            prg_16k_bank: 0,
+            prg_8k_bank: 0,
+            chr_banks: [0; 8],
+            banking_mode: 0,
+            mirroring: rom.mirroring,
+            irq: VrcIrq::default(),
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.banking_mode & 0x80 != 0 && !self.prg_ram.is_empty()
+    }
+
+    fn prg_address(&self, address: u16) -> usize {
+        let offset = address as usize - 0x8000;
+        let address = match address {
+            0x8000..=0xBFFF => self.prg_16k_bank * 2 * PRG_BANK_LEN + offset,
+            0xC000..=0xDFFF => self.prg_8k_bank * PRG_BANK_LEN + offset % PRG_BANK_LEN,
+            _ => self.prg_rom.len() - PRG_BANK_LEN + offset % PRG_BANK_LEN,
+        };
+        address % self.prg_rom.len()
+    }
+
+    fn chr_address(&self, address: u16) -> usize {
+        let slot = address as usize / CHR_BANK_LEN;
+        // 2KB banks either take A10 from the PPU or repeat the same 1KB
+        let two_kb = |register: usize| {
+            let bank = self.chr_banks[register];
+            if self.banking_mode & 0x20 != 0 {
+                (bank & !0x01) | (slot & 0x01)
+            } else {
+                bank
+            }
+        };
+        let bank = match (self.banking_mode & 0x03, slot) {
+            (0, _) | (2..=3, 0..=3) => self.chr_banks[slot],
+            (1, _) => two_kb(slot / 2),
+            (_, _) => two_kb(4 + (slot - 4) / 2),
+        };
+        (bank * CHR_BANK_LEN + address as usize % CHR_BANK_LEN) % self.chr.len()
+    }
+}
+
+impl Mapper for Vrc6 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                self.prg_ram[(address - 0x6000) as usize % self.prg_ram.len()]
+            }
+            0x8000..=0xFFFF => self.prg_rom[self.prg_address(address)],
+            _ => {
+                warn!("Access to unmapped cartridge address: {:4X}", address);
+                0
+            }
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, value: u8) {
+        if (0x6000..=0x7FFF).contains(&address) {
+            if self.prg_ram_enabled() {
+                let len = self.prg_ram.len();
+                self.prg_ram[(address - 0x6000) as usize % len] = value;
+            }
+            return;
+        }
+
+        let register = if self.swapped_lines {
+            swap_lines(address)
+        } else {
+            address
+        } & 0xf003;
+        match register {
+            0x8000..=0x8003 => self.prg_16k_bank = (value & 0x0f) as usize,
+            0xB003 => {
+                self.banking_mode = value;
+                self.mirroring = match (value >> 2) & 0x03 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            // The sound registers, handled by the APU
+            0x9000..=0xB002 => {}
+            0xC000..=0xC003 => self.prg_8k_bank = (value & 0x1f) as usize,
+            0xD000..=0xD003 => self.chr_banks[(register & 0x03) as usize] = value as usize,
+            0xE000..=0xE003 => self.chr_banks[4 + (register & 0x03) as usize] = value as usize,
+            0xF000 => self.irq.write_latch(value),
+            0xF001 => self.irq.write_control(value),
+            0xF002 => self.irq.acknowledge(),
+            _ => {
+                warn!("Write to unmapped cartridge address: {:4X}", address);
+            }
+        }
+    }
+
+    fn chr_read(&mut self, address: u16) -> u8 {
+        self.chr[self.chr_address(address)]
+    }
+
+    fn chr_write(&mut self, address: u16, value: u8) {
+        if self.chr_ram {
+            let address = self.chr_address(address);
+            self.chr[address] = value;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio> {
+        Some(ExpansionAudio::Vrc6 {
+            swapped_lines: self.swapped_lines,
+        })
+    }
+}
+
+/// Translates a mapper 26 address to the mapper 24 layout.
+pub(crate) fn swap_lines(address: u16) -> u16 {
+    (address & !0x03) | ((address & 0x01) << 1) | ((address & 0x02) >> 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Vrc6, CHR_BANK_LEN, PRG_BANK_LEN};
+    use crate::cartridge::{ExpansionAudio, Mapper, Mirroring, RomImage};
+
+    fn board(swapped_lines: bool) -> Vrc6 {
+        Vrc6::new(
+            RomImage::banked(32, PRG_BANK_LEN, 256, CHR_BANK_LEN),
+            swapped_lines,
+        )
+    }
+
+    #[test]
+    fn test_prg_banks() {
+        let mut vrc6 = board(false);
+        vrc6.cpu_write(0x8000, 0x03);
+        vrc6.cpu_write(0xC000, 0x11);
+        assert_eq!(vrc6.cpu_read(0x8000), 6);
+        assert_eq!(vrc6.cpu_read(0xA000), 7);
+        assert_eq!(vrc6.cpu_read(0xC000), 0x11);
+        assert_eq!(vrc6.cpu_read(0xE000), 31);
+
+        vrc6.cpu_write(0x6000, 0x42);
+        assert_eq!(vrc6.cpu_read(0x6000), 0x00);
+        vrc6.cpu_write(0xB003, 0x80);
+        vrc6.cpu_write(0x6000, 0x42);
+        assert_eq!(vrc6.cpu_read(0x6000), 0x42);
+    }
+
+    #[test]
+    fn test_chr_banks() {
+        let mut vrc6 = board(false);
+        for (register, bank) in (0xD000..=0xD003).chain(0xE000..=0xE003).zip(10..) {
+            vrc6.cpu_write(register, bank);
+        }
+        assert_eq!(vrc6.chr_read(0x0000), 10);
+        assert_eq!(vrc6.chr_read(0x1C00), 17);
+
+        // 2KB banks, taking A10 from the PPU
+        vrc6.cpu_write(0xB003, 0x21);
+        assert_eq!(vrc6.chr_read(0x0000), 10);
+        assert_eq!(vrc6.chr_read(0x0400), 11);
+        assert_eq!(vrc6.chr_read(0x1800), 12);
+        assert_eq!(vrc6.chr_read(0x1C00), 13);
+
+        // 1KB banks below, 2KB banks above
+        vrc6.cpu_write(0xB003, 0x02);
+        assert_eq!(vrc6.chr_read(0x0C00), 13);
+        assert_eq!(vrc6.chr_read(0x1000), 14);
+        assert_eq!(vrc6.chr_read(0x1400), 14);
+        assert_eq!(vrc6.chr_read(0x1800), 15);
+    }
+
+    #[test]
+    fn test_mapper_26() {
+        let mut vrc6 = board(true);
+        // $B003 is at $B003 either way, $D001 moves to $D002
+        vrc6.cpu_write(0xB003, 0x04);
+        vrc6.cpu_write(0xD002, 0x09);
+        assert_eq!(vrc6.mirroring(), Mirroring::Horizontal);
+        assert_eq!(vrc6.chr_read(0x0400), 9);
+        assert_eq!(
+            vrc6.expansion_audio(),
+            Some(ExpansionAudio::Vrc6 {
+                swapped_lines: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_irq() {
+        let mut vrc6 = board(false);
+        vrc6.cpu_write(0xF000, 0xfe);
+        vrc6.cpu_write(0xF001, 0x06);
+        vrc6.clock();
+        assert!(!vrc6.irq_pending());
+        vrc6.clock();
+        assert!(vrc6.irq_pending());
+        vrc6.cpu_write(0xF002, 0x00);
+        assert!(!vrc6.irq_pending());
+    }
+}
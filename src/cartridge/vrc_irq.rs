@@ -0,0 +1,131 @@
+// Counts CPU cycles, and scales them to scanlines when not in cycle mode
+const PRESCALER_PERIOD: i16 = 341;
+
+/// The IRQ counter shared by Konami's VRC4, VRC6 and VRC7. It counts up from
+/// the latch and fires when it overflows, either once per CPU cycle or once
+/// per 113.667 of them to approximate a scanline.
+#[derive(Default)]
+pub(crate) struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enable_after_ack: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl VrcIrq {
+    pub(crate) fn write_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    pub(crate) fn write_latch_low(&mut self, value: u8) {
+        self.latch = (self.latch & 0xf0) | (value & 0x0f);
+    }
+
+    pub(crate) fn write_latch_high(&mut self, value: u8) {
+        self.latch = (self.latch & 0x0f) | (value << 4);
+    }
+
+    pub(crate) fn write_control(&mut self, value: u8) {
+        self.enable_after_ack = value & 0x01 != 0;
+        self.enabled = value & 0x02 != 0;
+        self.cycle_mode = value & 0x04 != 0;
+        self.pending = false;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+    }
+
+    pub(crate) fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    pub(crate) fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if self.cycle_mode {
+            self.clock_counter();
+            return;
+        }
+        self.prescaler -= 3;
+        if self.prescaler <= 0 {
+            self.prescaler += PRESCALER_PERIOD;
+            self.clock_counter();
+        }
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xff {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    pub(crate) fn pending(&self) -> bool {
+        self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VrcIrq;
+
+    #[test]
+    fn test_cycle_mode() {
+        let mut irq = VrcIrq::default();
+        irq.write_latch_low(0x0d);
+        irq.write_latch_high(0x0f);
+        irq.write_control(0x07);
+
+        irq.clock();
+        irq.clock();
+        assert!(!irq.pending());
+        irq.clock();
+        assert!(irq.pending());
+
+        // The counter reloads from the latch and keeps going
+        irq.acknowledge();
+        assert!(!irq.pending());
+        for _ in 0..3 {
+            irq.clock();
+        }
+        assert!(irq.pending());
+
+        irq.write_control(0x00);
+        assert!(!irq.pending());
+        for _ in 0..1000 {
+            irq.clock();
+        }
+        assert!(!irq.pending());
+    }
+
+    #[test]
+    fn test_scanline_mode() {
+        let mut irq = VrcIrq::default();
+        irq.write_latch_low(0x0e);
+        irq.write_latch_high(0x0f);
+        irq.write_control(0x02);
+
+        // Two scanlines are 227 or 228 CPU cycles
+        for _ in 0..227 {
+            irq.clock();
+        }
+        assert!(!irq.pending());
+        irq.clock();
+        assert!(irq.pending());
+
+        // Without A, acknowledging disables the counter
+        irq.acknowledge();
+        for _ in 0..1000 {
+            irq.clock();
+        }
+        assert!(!irq.pending());
+    }
+}
@@ -1,85 +1,751 @@
-use std::{cell::RefCell, rc::Rc};
+use alloc::{
+    boxed::Box,
+    collections::BTreeSet,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{cell::RefCell, error::Error, fmt, ops::RangeInclusive};
 
 use assert_matches::debug_assert_matches;
 
 use bitflags::bitflags;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::{
     bus::Bus,
-    opcodes::{Address, AddressingMode, OPCODE_TABLE},
+    opcodes::{Address, AddressingMode, OpCode, Operation, OPCODE_TABLE},
 };
 
 bitflags! {
-    #[derive(Copy, Clone, Debug)]
-    struct StatusFlags: u8 {
+    /// The P register.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct StatusFlags: u8 {
+        /// Carry
         const C = 1;
+        /// Zero
         const Z = 1 << 1;
+        /// Interrupt disable
         const I = 1 << 2;
+        /// Decimal
         const D = 1 << 3;
+        /// Break, only exists on the stack
         const B = 1 << 4;
+        /// Unused, always set on the stack
         const X = 1 << 5;
+        /// Overflow
         const O = 1 << 6;
+        /// Negative
         const N = 1 << 7;
     }
 }
 
-pub struct CPU {
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CpuState {
+    Running,
+    /// A KIL opcode was executed. Only a reset gets the CPU going again.
+    Jammed,
+}
+
+/// Why a runner returned control to the caller.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The next instruction is at a breakpoint.
+    Breakpoint(u16),
+    /// The instruction at `pc` accessed a watched address.
+    Watchpoint {
+        address: u16,
+        access: Access,
+        pc: u16,
+    },
+    /// The condition given to `run_until` holds.
+    Condition,
+}
+
+/// What a call to `CPU::step` did.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Instruction(Instruction),
+    /// A pending NMI or IRQ was serviced.
+    Interrupt {
+        cycles: u64,
+    },
+}
+
+impl Step {
+    /// CPU cycles the step took, including any OAM DMA it triggered.
+    pub fn cycles(&self) -> u64 {
+        match self {
+            Step::Instruction(instruction) => instruction.cycles,
+            Step::Interrupt { cycles } => *cycles,
+        }
+    }
+}
+
+/// An executed instruction, as decoded by the CPU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u16,
+    pub opcode: u8,
+    pub mnemonic: &'static str,
+    /// The memory operand, or the target of a jump or a branch, taken or
+    /// not.
+    pub effective_address: Option<u16>,
+    /// Size of the instruction in bytes.
+    pub len: u16,
+    pub cycles: u64,
+}
+
+/// An error that stopped the CPU from executing an instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CpuError {
+    /// A KIL opcode at this address jammed the CPU.
+    Jammed(u16),
+    /// The instruction at `pc` was decoded with an addressing mode its
+    /// operation can't use.
+    InvalidAddressing { pc: u16 },
+    /// Strict mode is on and the instruction at `pc` is unofficial.
+    IllegalOpcode { opcode: u8, pc: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::Jammed(pc) => write!(f, "CPU jammed by KIL at ${:04X}", pc),
+            CpuError::InvalidAddressing { pc } => {
+                write!(
+                    f,
+                    "invalid addressing mode for the instruction at ${:04X}",
+                    pc
+                )
+            }
+            CpuError::IllegalOpcode { opcode, pc } => {
+                write!(f, "illegal opcode ${:02X} at ${:04X}", opcode, pc)
+            }
+        }
+    }
+}
+
+impl Error for CpuError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Receives a nestest-style trace line before each instruction executes.
+pub trait TraceSink {
+    fn trace(&mut self, line: &str);
+}
+
+impl<F: FnMut(&str)> TraceSink for F {
+    fn trace(&mut self, line: &str) {
+        self(line)
+    }
+}
+
+/// Callbacks around every executed instruction. Both receive the registers
+/// at that point: before the instruction, PC points at its opcode.
+pub trait InstructionHook {
+    fn before(&mut self, _registers: &CpuSnapshot, _opcode: u8) {}
+
+    fn after(&mut self, _registers: &CpuSnapshot, _instruction: &Instruction) {}
+}
+
+/// Registers, flags and cycle counters of the CPU, everything but the bus.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CpuSnapshot {
+    pub accumulator: u8,
+    pub x_register: u8,
+    pub y_register: u8,
+    pub program_counter: u16,
+    pub stack_pointer: u8,
+    pub status: u8,
+    pub total_cycles: u64,
+    pub nmi_pending: bool,
+    pub irq_pending: bool,
+    pub interrupt_polled: bool,
+    pub service_interrupt: bool,
+    pub state: CpuState,
+}
+
+/// Register values and cycle count the CPU starts with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PowerUpState {
+    pub accumulator: u8,
+    pub x_register: u8,
+    pub y_register: u8,
+    pub stack_pointer: u8,
+    pub status: u8,
+    pub total_cycles: u64,
+}
+
+impl PowerUpState {
+    /// The state nestest's automated mode expects at $C000: right after the
+    /// 7 cycles of the reset sequence.
+    pub fn nestest() -> Self {
+        Self {
+            total_cycles: 7,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for PowerUpState {
+    /// The state of the console at power-on, before the reset sequence.
+    fn default() -> Self {
+        Self {
+            accumulator: 0x00,
+            x_register: 0x00,
+            y_register: 0x00,
+            stack_pointer: 0xfd,
+            status: 0x24,
+            total_cycles: 0,
+        }
+    }
+}
+
+/// A 2A03 CPU, generic over the bus it drives. By default the bus is a
+/// shared `dyn Bus`, for callers that need to swap it at runtime.
+pub struct CPU<B = Rc<RefCell<dyn Bus>>> {
     accumulator: u8,
     x_register: u8,
     y_register: u8,
     program_counter: u16,
-    remaining_cycles: u8,
-    bus: Rc<RefCell<dyn Bus>>,
+    bus: B,
     status: StatusFlags,
     total_cycles: u64,
     stack_pointer: u8,
+    nmi_pending: bool,
+    irq_pending: bool,
+    // Interrupt lines as sampled at the end of the last cycle, and of the
+    // cycle before it. The latter decides whether an interrupt is serviced
+    // once the current instruction completes.
+    interrupt_polled: bool,
+    service_interrupt: bool,
+    state: CpuState,
+    trace_sink: Option<Box<dyn TraceSink>>,
+    hooks: Vec<Box<dyn InstructionHook>>,
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<(RangeInclusive<u16>, Access)>,
+    watchpoint_hit: Option<StopReason>,
+    decimal_mode: bool,
+    strict: bool,
+    // Address of the instruction being executed
+    instruction_address: u16,
 }
 
-impl CPU {
-    pub fn new(pc: u16, bus: Rc<RefCell<dyn Bus>>) -> Self {
+impl<B: Bus> CPU<B> {
+    pub fn new(pc: u16, bus: B) -> Self {
+        Self::with_power_up_state(pc, bus, PowerUpState::default())
+    }
+
+    pub fn with_power_up_state(pc: u16, bus: B, power_up: PowerUpState) -> Self {
         Self {
-            accumulator: 0x00,
-            x_register: 0x00,
-            y_register: 0x00,
+            accumulator: power_up.accumulator,
+            x_register: power_up.x_register,
+            y_register: power_up.y_register,
             program_counter: pc,
-            remaining_cycles: 0,
-            total_cycles: 0,
-            stack_pointer: 0xfd,
+            total_cycles: power_up.total_cycles,
+            stack_pointer: power_up.stack_pointer,
             bus,
-            status: StatusFlags::from_bits_truncate(0x24),
+            status: StatusFlags::from_bits_truncate(power_up.status),
+            nmi_pending: false,
+            irq_pending: false,
+            interrupt_polled: false,
+            service_interrupt: false,
+            state: CpuState::Running,
+            trace_sink: None,
+            hooks: vec![],
+            breakpoints: BTreeSet::new(),
+            watchpoints: vec![],
+            watchpoint_hit: None,
+            decimal_mode: false,
+            strict: false,
+            instruction_address: pc,
+        }
+    }
+
+    /// Resets the CPU: jumps through the $FFFC reset vector with interrupts
+    /// disabled. Like on hardware, the sequence takes 7 cycles.
+    pub fn reset(&mut self) {
+        // Reset goes through the motions of an interrupt, but the stack
+        // accesses are reads
+        self.read(self.program_counter);
+        self.read(self.program_counter);
+        for offset in 0..3 {
+            self.read(STACK_PAGE + u16::from(self.stack_pointer.wrapping_sub(offset)));
+        }
+
+        self.stack_pointer = 0xfd;
+        self.status |= StatusFlags::I;
+        self.nmi_pending = false;
+        self.state = CpuState::Running;
+        self.program_counter = self.read16(RESET_VECTOR);
+        // Interrupts polled before the reset are lost with it
+        self.interrupt_polled = false;
+        self.service_interrupt = false;
+    }
+
+    pub fn state(&self) -> CpuState {
+        self.state
+    }
+
+    pub fn status(&self) -> StatusFlags {
+        self.status
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+
+    /// Enables BCD arithmetic in ADC and SBC while the D flag is set. The
+    /// 2A03 lacks it, so it is disabled by default.
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.decimal_mode = enabled;
+    }
+
+    /// In strict mode, unofficial opcodes fail with
+    /// `CpuError::IllegalOpcode` instead of being emulated.
+    pub fn set_strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Streams a trace of every executed instruction to `sink`, or stops
+    /// tracing when `None` is given.
+    pub fn set_trace_sink(&mut self, sink: Option<Box<dyn TraceSink>>) {
+        self.trace_sink = sink;
+    }
+
+    pub fn add_hook(&mut self, hook: Box<dyn InstructionHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn clear_hooks(&mut self) {
+        self.hooks.clear();
+    }
+
+    pub fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            accumulator: self.accumulator,
+            x_register: self.x_register,
+            y_register: self.y_register,
+            program_counter: self.program_counter,
+            stack_pointer: self.stack_pointer,
+            status: self.status.bits(),
+            total_cycles: self.total_cycles,
+            nmi_pending: self.nmi_pending,
+            irq_pending: self.irq_pending,
+            interrupt_polled: self.interrupt_polled,
+            service_interrupt: self.service_interrupt,
+            state: self.state,
         }
     }
 
-    fn cycle(&mut self) {
-        if self.remaining_cycles == 0 {
-            let opcode = self.bus.read(self.program_counter);
+    /// Restores a snapshot taken with `snapshot`. The bus is left untouched.
+    pub fn restore(&mut self, snapshot: &CpuSnapshot) {
+        self.accumulator = snapshot.accumulator;
+        self.x_register = snapshot.x_register;
+        self.y_register = snapshot.y_register;
+        self.program_counter = snapshot.program_counter;
+        self.stack_pointer = snapshot.stack_pointer;
+        self.status = StatusFlags::from_bits_truncate(snapshot.status);
+        self.total_cycles = snapshot.total_cycles;
+        self.nmi_pending = snapshot.nmi_pending;
+        self.irq_pending = snapshot.irq_pending;
+        self.interrupt_polled = snapshot.interrupt_polled;
+        self.service_interrupt = snapshot.service_interrupt;
+        self.state = snapshot.state;
+    }
+
+    /// Signals a non-maskable interrupt. Like on hardware, interrupts are
+    /// polled before the last cycle of an instruction, so the NMI is serviced
+    /// once the next instruction completes.
+    pub fn nmi(&mut self) {
+        self.nmi_pending = true;
+    }
+
+    /// Asserts the IRQ line. The line is level triggered: it stays asserted
+    /// until `clear_irq` is called, and is only serviced while the I flag is
+    /// clear.
+    pub fn assert_irq(&mut self) {
+        self.irq_pending = true;
+    }
 
-            self.program_counter += 1;
+    pub fn clear_irq(&mut self) {
+        self.irq_pending = false;
+    }
 
-            let op = OPCODE_TABLE[opcode as usize];
+    /// Executes a single instruction, or services a pending interrupt. Fails
+    /// once the CPU has jammed, until it is reset.
+    pub fn step(&mut self) -> Result<Step, CpuError> {
+        if self.state == CpuState::Jammed {
+            return Err(CpuError::Jammed(self.program_counter));
+        }
 
-            let address = self.resolve_address(op.addressing());
+        self.instruction_address = self.program_counter;
+        let start = self.total_cycles;
 
-            self.program_counter += op.len() - 1;
+        if self.service_interrupt {
+            self.interrupt();
+            return Ok(Step::Interrupt {
+                cycles: self.total_cycles - start,
+            });
+        }
 
-            op.execute(self, address);
+        if self.trace_sink.is_some() {
+            let line = self.trace();
+            if let Some(sink) = self.trace_sink.as_mut() {
+                sink.trace(&line);
+            }
+        }
 
-            self.remaining_cycles += op.cycles();
+        if !self.hooks.is_empty() {
+            let registers = self.snapshot();
+            let opcode = self.bus.peek(self.program_counter);
+            for hook in self.hooks.iter_mut() {
+                hook.before(&registers, opcode);
+            }
         }
-        self.total_cycles += 1;
-        self.remaining_cycles -= 1;
+
+        let (opcode, address) = self.execute_next()?;
+        let op = OPCODE_TABLE[opcode as usize];
+
+        let effective_address = match address {
+            Address::Absolute(address, _) => Some(address),
+            Address::Relative(offset) => {
+                let next = self.instruction_address.wrapping_add(op.len());
+                Some(s8_to_u16(offset).wrapping_add(next))
+            }
+            // JSR only learns its target while executing
+            _ if op.operation() == Operation::Jsr => Some(self.program_counter),
+            _ => None,
+        };
+
+        let instruction = Instruction {
+            address: self.instruction_address,
+            opcode,
+            mnemonic: op.name(),
+            effective_address,
+            len: op.len(),
+            cycles: self.total_cycles - start,
+        };
+
+        if !self.hooks.is_empty() {
+            let registers = self.snapshot();
+            for hook in self.hooks.iter_mut() {
+                hook.after(&registers, &instruction);
+            }
+        }
+
+        Ok(Step::Instruction(instruction))
+    }
+
+    /// Runs `count` steps like `step` does, and returns how many cycles they
+    /// took. Unless a trace sink or hooks are installed, no per-instruction
+    /// metadata is built.
+    pub fn run_instructions(&mut self, count: u64) -> Result<u64, CpuError> {
+        let start = self.total_cycles;
+        if self.trace_sink.is_some() || !self.hooks.is_empty() {
+            for _ in 0..count {
+                self.step()?;
+            }
+        } else {
+            for _ in 0..count {
+                if self.state == CpuState::Jammed {
+                    return Err(CpuError::Jammed(self.program_counter));
+                }
+
+                self.instruction_address = self.program_counter;
+                if self.service_interrupt {
+                    self.interrupt();
+                } else {
+                    self.execute_next()?;
+                }
+            }
+        }
+        Ok(self.total_cycles - start)
+    }
+
+    // Fetches, decodes and executes the instruction at PC, followed by the
+    // OAM DMA it may have started
+    fn execute_next(&mut self) -> Result<(u8, Address), CpuError> {
+        let start = self.total_cycles;
+
+        let opcode = self.fetch();
+
+        let op = OPCODE_TABLE[opcode as usize];
+
+        if self.strict && op.unofficial() {
+            // Leave PC on the offending opcode
+            self.program_counter = self.instruction_address;
+            return Err(CpuError::IllegalOpcode {
+                opcode,
+                pc: self.instruction_address,
+            });
+        }
+
+        let address = self.resolve_address(&op);
+
+        self.execute(op.operation(), address)?;
+
+        // Page crosses and taken branches can only add cycles
+        debug_assert!(self.total_cycles - start >= u64::from(op.cycles()));
+
+        if let Some(page) = self.bus.take_oam_dma() {
+            self.oam_dma(page);
+        }
+
+        Ok((opcode, address))
+    }
+
+    // The CPU is halted for 513 cycles, or 514 when the copy has to wait for
+    // a read cycle, while 256 bytes are copied from the page to OAMDATA
+    fn oam_dma(&mut self, page: u8) {
+        self.read(self.program_counter);
+        if self.total_cycles & 1 == 0 {
+            self.read(self.program_counter);
+        }
+
+        let start = u16::from(page) << 8;
+        for offset in 0..=0xff {
+            let value = self.read(start | offset);
+            self.write(OAM_DATA, value);
+        }
+    }
+
+    /// Runs until a BRK instruction has been executed or the CPU jams.
+    pub fn run_until_brk(&mut self) -> Result<(), CpuError> {
+        loop {
+            let opcode = self.bus.peek(self.program_counter);
+            self.step()?;
+            if opcode == BRK_OPCODE {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Executes whole instructions until at least `cycles` CPU cycles have
+    /// elapsed. Returns how many cycles past `cycles` the last instruction
+    /// ran.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> Result<u64, CpuError> {
+        let target = self.total_cycles + cycles;
+        while self.total_cycles < target {
+            self.run_instructions(1)?;
+        }
+        Ok(self.total_cycles - target)
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&address);
+    }
+
+    /// Watches `addresses` for the given kind of access. Dummy accesses count
+    /// too, since they have the same side effects as real ones.
+    pub fn add_watchpoint(&mut self, addresses: RangeInclusive<u16>, access: Access) {
+        self.watchpoints.push((addresses, access));
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Runs until the next instruction is at a breakpoint, without executing
+    /// it, or an instruction touches a watchpoint. The instruction at PC
+    /// always executes, so execution can resume from a breakpoint.
+    pub fn step_until_break(&mut self) -> Result<StopReason, CpuError> {
+        self.run_until(|_, _| false)
+    }
+
+    /// Like `step_until_break`, but also stops once `condition` holds after
+    /// an instruction, e.g. on a PC value, a memory location or a cycle
+    /// budget.
+    pub fn run_until(
+        &mut self,
+        mut condition: impl FnMut(&Self, &B) -> bool,
+    ) -> Result<StopReason, CpuError> {
+        self.watchpoint_hit = None;
+        loop {
+            self.step()?;
+            if let Some(hit) = self.watchpoint_hit.take() {
+                return Ok(hit);
+            }
+            if self.breakpoints.contains(&self.program_counter) {
+                return Ok(StopReason::Breakpoint(self.program_counter));
+            }
+            if condition(self, &self.bus) {
+                return Ok(StopReason::Condition);
+            }
+        }
+    }
+
+    // Every bus access takes exactly one CPU cycle, so the rest of the system
+    // is clocked in between the accesses of an instruction.
+    fn read(&mut self, address: u16) -> u8 {
+        self.watch(address, Access::Read);
+        let value = self.bus.read(address);
+        self.tick();
+        value
+    }
+
+    fn read16(&mut self, address: u16) -> u16 {
+        let lo = u16::from(self.read(address));
+        let hi = u16::from(self.read(address.wrapping_add(1)));
+        (hi << 8) | lo
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.watch(address, Access::Write);
+        self.bus.write(address, value);
+        self.tick();
     }
 
-    pub fn step(&mut self) {
-        self.cycle();
-        while self.remaining_cycles != 0 {
-            self.cycle();
+    // Records the first watchpoint hit of an instruction
+    fn watch(&mut self, address: u16, access: Access) {
+        if self.watchpoint_hit.is_some() {
+            return;
+        }
+
+        let watched = self
+            .watchpoints
+            .iter()
+            .any(|(addresses, kind)| *kind == access && addresses.contains(&address));
+
+        if watched {
+            self.watchpoint_hit = Some(StopReason::Watchpoint {
+                address,
+                access,
+                pc: self.instruction_address,
+            });
         }
     }
 
-    pub fn run_until_brk(&mut self) {
-        while !self.status.contains(StatusFlags::B) {
-            self.step()
+    fn tick(&mut self) {
+        self.total_cycles += 1;
+        self.bus.tick();
+
+        self.service_interrupt = self.interrupt_polled;
+        let irq = self.irq_pending || self.bus.irq();
+        self.interrupt_polled = self.nmi_pending || (irq && !self.status.contains(StatusFlags::I));
+    }
+
+    // Reads the next byte of the instruction stream
+    fn fetch(&mut self) -> u8 {
+        let value = self.read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        value
+    }
+
+    fn fetch16(&mut self) -> u16 {
+        let lo = u16::from(self.fetch());
+        let hi = u16::from(self.fetch());
+        (hi << 8) | lo
+    }
+
+    fn execute(&mut self, operation: Operation, address: Address) -> Result<(), CpuError> {
+        match operation {
+            Operation::Adc => self.adc(address),
+            Operation::Ahx => self.ahx(address),
+            Operation::Alr => self.alr(address),
+            Operation::Anc => self.anc(address),
+            Operation::And => self.and(address),
+            Operation::Arr => self.arr(address),
+            Operation::Asl => self.asl(address),
+            Operation::Axs => self.axs(address),
+            Operation::Bcc => self.bcc(address),
+            Operation::Bcs => self.bcs(address),
+            Operation::Beq => self.beq(address),
+            Operation::Bit => self.bit(address),
+            Operation::Bmi => self.bmi(address),
+            Operation::Bne => self.bne(address),
+            Operation::Bpl => self.bpl(address),
+            Operation::Brk => self.brk(address),
+            Operation::Bvc => self.bvc(address),
+            Operation::Bvs => self.bvs(address),
+            Operation::Clc => self.clc(address),
+            Operation::Cld => self.cld(address),
+            Operation::Cli => self.cli(address),
+            Operation::Clv => self.clv(address),
+            Operation::Cmp => self.cmp(address),
+            Operation::Cpx => self.cpx(address),
+            Operation::Cpy => self.cpy(address),
+            Operation::Dcp => self.dcp(address),
+            Operation::Dec => self.dec(address),
+            Operation::Dex => self.dex(address),
+            Operation::Dey => self.dey(address),
+            Operation::Eor => self.eor(address),
+            Operation::Inc => self.inc(address),
+            Operation::Inx => self.inx(address),
+            Operation::Iny => self.iny(address),
+            Operation::Isc => self.isc(address),
+            Operation::Jmp => self.jmp(address),
+            Operation::Jsr => self.jsr(address),
+            Operation::Kil => self.kil(address),
+            Operation::Las => self.las(address),
+            Operation::Lax => self.lax(address),
+            Operation::Lda => self.lda(address),
+            Operation::Ldx => self.ldx(address),
+            Operation::Ldy => self.ldy(address),
+            Operation::Lsr => self.lsr(address),
+            Operation::Nop => self.nop(address),
+            Operation::Ora => self.ora(address),
+            Operation::Pha => self.pha(address),
+            Operation::Php => self.php(address),
+            Operation::Pla => self.pla(address),
+            Operation::Plp => self.plp(address),
+            Operation::Rla => self.rla(address),
+            Operation::Rol => self.rol(address),
+            Operation::Ror => self.ror(address),
+            Operation::Rra => self.rra(address),
+            Operation::Rti => self.rti(address),
+            Operation::Rts => self.rts(address),
+            Operation::Sax => self.sax(address),
+            Operation::Sbc => self.sbc(address),
+            Operation::Sec => self.sec(address),
+            Operation::Sed => self.sed(address),
+            Operation::Sei => self.sei(address),
+            Operation::Shx => self.shx(address),
+            Operation::Shy => self.shy(address),
+            Operation::Slo => self.slo(address),
+            Operation::Sre => self.sre(address),
+            Operation::Sta => self.sta(address),
+            Operation::Stx => self.stx(address),
+            Operation::Sty => self.sty(address),
+            Operation::Tas => self.tas(address),
+            Operation::Tax => self.tax(address),
+            Operation::Tay => self.tay(address),
+            Operation::Tsx => self.tsx(address),
+            Operation::Txa => self.txa(address),
+            Operation::Txs => self.txs(address),
+            Operation::Tya => self.tya(address),
+            Operation::Xaa => self.xaa(address),
         }
     }
 
@@ -90,18 +756,30 @@ impl CPU {
     }
 
     pub fn trace(&self) -> String {
-        let opcode = self.bus.read(self.program_counter);
+        let opcode = self.bus.peek(self.program_counter);
 
         let op = OPCODE_TABLE[opcode as usize];
 
-        let hexdump = self.hexdump(self.program_counter, self.program_counter + op.len());
+        let hexdump = self.hexdump(self.program_counter, op.len());
 
-        let asm = format!("{}{:28}", op.name(), " ");
-        let ppu = " ".repeat(11);
+        // Strict mode marks the unofficial opcodes it refuses to run
+        let prefix = match (op.unofficial(), self.strict) {
+            (true, false) => '*',
+            (true, true) => '!',
+            (false, _) => ' ',
+        };
+        let asm = format!("{} {}", op.name(), self.disassemble(&op));
+        let ppu = match self.bus.ppu_position() {
+            Some((scanline, dot)) => format!("PPU:{:3},{:3}", scanline, dot),
+            None => " ".repeat(11),
+        };
+        // Lines stay nestest's own outside of strict mode
+        let mode = if self.strict { " STRICT" } else { "" };
         format!(
-            "{:04X}  {:9} {} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} {} CYC:{}",
+            "{:04X}  {:9}{}{:31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} {} CYC:{}{}",
             self.program_counter,
             hexdump,
+            prefix,
             asm,
             self.accumulator,
             self.x_register,
@@ -109,15 +787,116 @@ impl CPU {
             self.status.bits(),
             self.stack_pointer,
             ppu,
-            self.total_cycles + 7
-        ) // TODO figure this out
+            self.total_cycles,
+            mode
+        )
+    }
+
+    // Formats the operand of the instruction at PC like nestest does:
+    // memory operands are followed by the effective address and the value
+    // stored there.
+    fn disassemble(&self, op: &OpCode) -> String {
+        let operand_address = self.program_counter.wrapping_add(1);
+        let byte = self.bus.peek(operand_address);
+        let word = self.bus.peek16(operand_address);
+
+        match op.addressing() {
+            AddressingMode::Implied => match op.name() {
+                "ASL" | "LSR" | "ROL" | "ROR" => "A".to_string(),
+                _ => String::new(),
+            },
+            AddressingMode::Immediate => format!("#${:02X}", byte),
+            AddressingMode::ZeroPage => {
+                format!("${:02X} = {:02X}", byte, self.peek(u16::from(byte)))
+            }
+            AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+                let (register, offset) = match op.addressing() {
+                    AddressingMode::ZeroPageX => ('X', self.x_register),
+                    _ => ('Y', self.y_register),
+                };
+                let address = byte.wrapping_add(offset);
+                format!(
+                    "${:02X},{} @ {:02X} = {:02X}",
+                    byte,
+                    register,
+                    address,
+                    self.peek(u16::from(address))
+                )
+            }
+            AddressingMode::Absolute => match op.name() {
+                "JMP" | "JSR" => format!("${:04X}", word),
+                _ => format!("${:04X} = {:02X}", word, self.peek(word)),
+            },
+            AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+                let (register, offset) = match op.addressing() {
+                    AddressingMode::AbsoluteX => ('X', self.x_register),
+                    _ => ('Y', self.y_register),
+                };
+                let address = word.wrapping_add(u16::from(offset));
+                format!(
+                    "${:04X},{} @ {:04X} = {:02X}",
+                    word,
+                    register,
+                    address,
+                    self.peek(address)
+                )
+            }
+            AddressingMode::Indirect => {
+                // JMP ($xxFF) reads the high byte from the start of the page
+                let lo = u16::from(self.peek(word));
+                let hi = u16::from(self.peek((word & 0xff00) | (word.wrapping_add(1) & 0xff)));
+                format!("(${:04X}) = {:04X}", word, (hi << 8) | lo)
+            }
+            AddressingMode::IndirectX => {
+                let pointer = byte.wrapping_add(self.x_register);
+                let address = self.peek16_zero_page(pointer);
+                format!(
+                    "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
+                    byte,
+                    pointer,
+                    address,
+                    self.peek(address)
+                )
+            }
+            AddressingMode::IndirectY => {
+                let base = self.peek16_zero_page(byte);
+                let address = base.wrapping_add(u16::from(self.y_register));
+                format!(
+                    "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+                    byte,
+                    base,
+                    address,
+                    self.peek(address)
+                )
+            }
+            AddressingMode::Relative => {
+                let target = s8_to_u16(byte).wrapping_add(self.program_counter.wrapping_add(2));
+                format!("${:04X}", target)
+            }
+        }
+    }
+
+    // Like the nestest log, the trace shows I/O registers as $FF rather than
+    // their contents
+    fn peek(&self, address: u16) -> u8 {
+        match address {
+            0x2000..=0x401F => 0xFF,
+            _ => self.bus.peek(address),
+        }
+    }
+
+    fn peek16_zero_page(&self, address: u8) -> u16 {
+        let lo = u16::from(self.bus.peek(u16::from(address)));
+        let hi = u16::from(self.bus.peek(u16::from(address.wrapping_add(1))));
+        (hi << 8) | lo
     }
 
     // TODO: consider if this should be in the Bus trait instead
-    fn hexdump(&self, start: u16, end: u16) -> String {
+    fn hexdump(&self, start: u16, len: u16) -> String {
         let mut hexdump = String::new();
-        for addr in start..end {
-            hexdump.push_str(&format!("{:02X} ", self.bus.read(addr)));
+        for offset in 0..len {
+            let value = self.bus.peek(start.wrapping_add(offset));
+            hexdump.push_str(&format!("{:02X} ", value));
         }
         hexdump
     }
@@ -128,397 +907,434 @@ fn s8_to_u16(value: u8) -> u16 {
     if value & 0x80 > 0 {
         value |= 0xff00;
     }
-    return value;
+    value
 }
 
 const STACK_PAGE: u16 = 0x0100;
+const OAM_DATA: u16 = 0x2004;
+const NMI_VECTOR: u16 = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const IRQ_VECTOR: u16 = 0xFFFE;
+const BRK_OPCODE: u8 = 0x00;
+// The "magic" constant of XAA varies between chips, $EE is the most common
+const XAA_MAGIC: u8 = 0xEE;
 
 // Operations
-impl CPU {
-    pub(crate) fn adc(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_cross) => {
-            let value = self.bus.read(address);
-            let carry = self.status.contains(StatusFlags::C) as u16;
-            let result: u16 = u16::from(self.accumulator) + u16::from(value) + carry;
-            let result_u8 = result as u8;
-
-            self.status.set(StatusFlags::C, result > u16::from(u8::max_value()));
-            self.status.set(
-                StatusFlags::O,
-                (!(self.accumulator ^ value)
-                    & (self.accumulator ^ result_u8)
-                    & StatusFlags::N.bits())
-                    > 0,
-            );
-            self.set_zero_or_neg_flags(result_u8);
-
-            if page_cross {
-                self.remaining_cycles += 1;
-            }
+impl<B: Bus> CPU<B> {
+    fn adc(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
 
-            self.accumulator = result_u8;
-        });
+        if self.decimal_mode && self.status.contains(StatusFlags::D) {
+            self.adc_decimal(value);
+            return Ok(());
+        }
+
+        let carry = self.status.contains(StatusFlags::C) as u16;
+        let result: u16 = u16::from(self.accumulator) + u16::from(value) + carry;
+        let result_u8 = result as u8;
+
+        self.status.set(StatusFlags::C, result > u16::from(u8::MAX));
+        self.status.set(
+            StatusFlags::O,
+            (!(self.accumulator ^ value) & (self.accumulator ^ result_u8) & StatusFlags::N.bits())
+                > 0,
+        );
+        self.set_zero_or_neg_flags(result_u8);
+
+        self.accumulator = result_u8;
+        Ok(())
     }
 
-    pub(crate) fn ahx(&mut self, _address: Address) {
-        todo!("ahx Not Implemented")
+    // NMOS 6502 BCD addition: Z comes from the binary sum, N and V from the
+    // sum before the high digit is adjusted
+    fn adc_decimal(&mut self, value: u8) {
+        let carry = self.status.contains(StatusFlags::C) as u8;
+        let binary = self.accumulator.wrapping_add(value).wrapping_add(carry);
+
+        let mut lo = (self.accumulator & 0x0f) + (value & 0x0f) + carry;
+        if lo > 0x09 {
+            lo += 0x06;
+        }
+        let mut hi = (self.accumulator >> 4) + (value >> 4) + (lo > 0x0f) as u8;
+
+        let intermediate = (hi << 4) | (lo & 0x0f);
+        self.status.set(StatusFlags::Z, binary == 0);
+        self.status
+            .set(StatusFlags::N, intermediate & StatusFlags::N.bits() != 0);
+        self.status.set(
+            StatusFlags::O,
+            !(self.accumulator ^ value) & (self.accumulator ^ intermediate) & StatusFlags::N.bits()
+                != 0,
+        );
+
+        if hi > 0x09 {
+            hi += 0x06;
+        }
+        self.status.set(StatusFlags::C, hi > 0x0f);
+
+        self.accumulator = (hi << 4) | (lo & 0x0f);
     }
 
-    pub(crate) fn alr(&mut self, address: Address) {
-        self.and(address);
-        self.lsr(Address::Implied);
+    fn ahx(&mut self, address: Address) -> Result<(), CpuError> {
+        self.store_high(address, self.accumulator & self.x_register)
     }
 
-    pub(crate) fn anc(&mut self, address: Address) {
-        self.and(address);
-        self.status.set(StatusFlags::C, self.accumulator >> 7 == 1);
+    fn alr(&mut self, address: Address) -> Result<(), CpuError> {
+        self.and(address)?;
+        self.lsr(Address::Implied)
     }
 
-    pub(crate) fn and(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_cross) => {
-            let value = self.bus.read(address);
-            self.accumulator &= value;
-            self.set_zero_or_neg_flags(self.accumulator);
-            if page_cross {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn anc(&mut self, address: Address) -> Result<(), CpuError> {
+        self.and(address)?;
+        self.status.set(StatusFlags::C, self.accumulator >> 7 == 1);
+        Ok(())
     }
 
-    pub(crate) fn arr(&mut self, _address: Address) {
-        todo!("arr Not Implemented")
+    fn and(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        self.accumulator &= value;
+        self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn asl(&mut self, address: Address) {
-        let mut inner = |value: u8| -> u8 {
-            self.status.set(StatusFlags::C, value >> 7 == 1);
-            let value = value << 1;
-            self.status.set(StatusFlags::Z, value == 0);
-            self.status
-                .set(StatusFlags::N, value & StatusFlags::N.bits() != 0);
-            value
-        };
+    fn arr(&mut self, address: Address) -> Result<(), CpuError> {
+        self.and(address)?;
+        self.ror(Address::Implied)?;
 
-        match address {
-            Address::Implied => self.accumulator = inner(self.accumulator),
-            Address::Absolute(address, _) => {
-                let value = inner(self.bus.read(address));
-                self.bus.write(address, value);
-            }
-            _ => panic!("ASL opcode with relative addressing"),
-        }
+        // C and V come from bits 6 and 5 of the result instead of the shift
+        let value = self.accumulator;
+        self.status.set(StatusFlags::C, value & 0x40 != 0);
+        self.status
+            .set(StatusFlags::O, ((value >> 6) ^ (value >> 5)) & 1 != 0);
+        Ok(())
     }
 
-    pub(crate) fn axs(&mut self, _address: Address) {
-        todo!("axs Not Implemented")
+    fn asl(&mut self, address: Address) -> Result<(), CpuError> {
+        self.read_modify_write(address, Self::shift_left)?;
+        Ok(())
     }
 
-    fn branch(&mut self, address: Address, cond: bool) {
-        debug_assert_matches!(address,
-        Address::Relative(address) => {
-            let address = s8_to_u16(address).wrapping_add(self.program_counter);
+    fn axs(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        let and = self.accumulator & self.x_register;
 
-            if cond {
-                if address & 0xFF00 != self.program_counter & 0xFF00 {
-                    self.remaining_cycles += 2;
-                } else {
-                    self.remaining_cycles += 1;
-                }
-                self.program_counter = address;
+        self.status.set(StatusFlags::C, and >= value);
+
+        self.x_register = and.wrapping_sub(value);
+        self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
+    }
+
+    fn branch(&mut self, address: Address, cond: bool) -> Result<(), CpuError> {
+        let offset = match address {
+            Address::Relative(offset) => offset,
+            _ => return Err(self.invalid_addressing()),
+        };
+
+        if cond {
+            let address = s8_to_u16(offset).wrapping_add(self.program_counter);
+
+            // A taken branch that stays on its page doesn't poll interrupts
+            // on its last cycle, so an interrupt that arrived during the
+            // branch waits for one more instruction
+            if self.interrupt_polled && !self.service_interrupt {
+                self.interrupt_polled = false;
+            }
+
+            // The next opcode is read while the offset is added, and again
+            // while the high byte is fixed when the branch crosses a page
+            self.read(self.program_counter);
+            if address & 0xFF00 != self.program_counter & 0xFF00 {
+                self.read((self.program_counter & 0xFF00) | (address & 0x00FF));
             }
-        });
+            self.program_counter = address;
+        }
+        Ok(())
     }
 
-    pub(crate) fn bcc(&mut self, address: Address) {
-        self.branch(address, !self.status.contains(StatusFlags::C));
+    fn bcc(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, !self.status.contains(StatusFlags::C))
     }
 
-    pub(crate) fn bcs(&mut self, address: Address) {
-        self.branch(address, self.status.contains(StatusFlags::C));
+    fn bcs(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, self.status.contains(StatusFlags::C))
     }
 
-    pub(crate) fn beq(&mut self, address: Address) {
-        self.branch(address, self.status.contains(StatusFlags::Z));
+    fn beq(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, self.status.contains(StatusFlags::Z))
     }
 
-    pub(crate) fn bit(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            let value = self.bus.read(address);
-            let mask = StatusFlags::from_bits_truncate(value);
+    fn bit(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        let mask = StatusFlags::from_bits_truncate(value);
 
-            self.status.set(StatusFlags::Z, self.accumulator & value == 0);
-            self.status.set(StatusFlags::O, mask.contains(StatusFlags::O));
-            self.status.set(StatusFlags::N, mask.contains(StatusFlags::N));
-        });
+        self.status
+            .set(StatusFlags::Z, self.accumulator & value == 0);
+        self.status
+            .set(StatusFlags::O, mask.contains(StatusFlags::O));
+        self.status
+            .set(StatusFlags::N, mask.contains(StatusFlags::N));
+        Ok(())
     }
 
-    pub(crate) fn bmi(&mut self, address: Address) {
-        self.branch(address, self.status.contains(StatusFlags::N));
+    fn bmi(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, self.status.contains(StatusFlags::N))
     }
 
-    pub(crate) fn bne(&mut self, address: Address) {
-        self.branch(address, !self.status.contains(StatusFlags::Z));
+    fn bne(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, !self.status.contains(StatusFlags::Z))
     }
 
-    pub(crate) fn bpl(&mut self, address: Address) {
-        self.branch(address, !self.status.contains(StatusFlags::N));
+    fn bpl(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, !self.status.contains(StatusFlags::N))
     }
 
-    pub(crate) fn brk(&mut self, address: Address) {
+    fn brk(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
-        self.status |= StatusFlags::B;
-        // TODO: stack manipulation
+        // BRK skips a padding byte, so the return address is PC + 2
+        self.program_counter = self.program_counter.wrapping_add(1);
+        self.push_stack_16(self.program_counter);
+        let vector = self.interrupt_vector();
+        self.push_stack((self.status | StatusFlags::B | StatusFlags::X).bits());
+        self.status |= StatusFlags::I;
+        self.program_counter = self.read16(vector);
+
+        // The first instruction of the handler always runs
+        self.service_interrupt = false;
+        Ok(())
     }
 
-    pub(crate) fn bvc(&mut self, address: Address) {
-        self.branch(address, !self.status.contains(StatusFlags::O));
+    fn bvc(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, !self.status.contains(StatusFlags::O))
     }
 
-    pub(crate) fn bvs(&mut self, address: Address) {
-        self.branch(address, self.status.contains(StatusFlags::O));
+    fn bvs(&mut self, address: Address) -> Result<(), CpuError> {
+        self.branch(address, self.status.contains(StatusFlags::O))
     }
 
-    pub(crate) fn clc(&mut self, address: Address) {
+    fn clc(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
         self.status -= StatusFlags::C;
+        Ok(())
     }
 
-    pub(crate) fn cld(&mut self, address: Address) {
+    fn cld(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.status -= StatusFlags::D;
+        Ok(())
     }
 
-    pub(crate) fn cli(&mut self, address: Address) {
+    fn cli(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.status -= StatusFlags::I;
+        Ok(())
     }
 
-    pub(crate) fn clv(&mut self, address: Address) {
+    fn clv(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.status -= StatusFlags::O;
+        Ok(())
     }
 
-    fn compare(&mut self, address: Address, register_value: u8) {
-        debug_assert_matches!(address, Address::Absolute(address, page_cross) => {
-            let value = self.bus.read(address);
+    fn compare(&mut self, address: Address, register_value: u8) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
 
-            self.status.set(StatusFlags::C, register_value >= value);
+        self.status.set(StatusFlags::C, register_value >= value);
 
-            let cmp = register_value.wrapping_sub(value);
-            self.set_zero_or_neg_flags(cmp);
-
-            if page_cross {
-                self.remaining_cycles += 1;
-            }
-        });
+        let cmp = register_value.wrapping_sub(value);
+        self.set_zero_or_neg_flags(cmp);
+        Ok(())
     }
 
-    pub(crate) fn cmp(&mut self, address: Address) {
-        self.compare(address, self.accumulator);
+    fn cmp(&mut self, address: Address) -> Result<(), CpuError> {
+        self.compare(address, self.accumulator)
     }
 
-    pub(crate) fn cpx(&mut self, address: Address) {
-        self.compare(address, self.x_register);
+    fn cpx(&mut self, address: Address) -> Result<(), CpuError> {
+        self.compare(address, self.x_register)
     }
 
-    pub(crate) fn cpy(&mut self, address: Address) {
-        self.compare(address, self.y_register);
+    fn cpy(&mut self, address: Address) -> Result<(), CpuError> {
+        self.compare(address, self.y_register)
     }
 
-    pub(crate) fn dcp(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            self.dec(Address::Absolute(address, false));
-            self.cmp(Address::Absolute(address, false));
-        });
+    fn dcp(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::decrement)?;
+        self.cmp(Address::Immediate(value))
     }
 
-    pub(crate) fn dec(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            let value = self.bus.read(address).wrapping_sub(1);
-            self.set_zero_or_neg_flags(value);
-            self.bus.write(address, value);
-        });
+    fn dec(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Absolute(_, _));
+
+        self.read_modify_write(address, Self::decrement)?;
+        Ok(())
     }
 
-    pub(crate) fn dex(&mut self, address: Address) {
+    fn dex(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.x_register = self.x_register.wrapping_sub(1);
         self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn dey(&mut self, address: Address) {
+    fn dey(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.y_register = self.y_register.wrapping_sub(1);
         self.set_zero_or_neg_flags(self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn eor(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            let value = self.bus.read(address);
-            self.accumulator ^= value;
-            self.set_zero_or_neg_flags(self.accumulator);
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn eor(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        self.accumulator ^= value;
+        self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn inc(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            let value = self.bus.read(address).wrapping_add(1);
-            self.set_zero_or_neg_flags(value);
-            self.bus.write(address, value);
-        });
+    fn inc(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Absolute(_, _));
+
+        self.read_modify_write(address, Self::increment)?;
+        Ok(())
     }
 
-    pub(crate) fn inx(&mut self, address: Address) {
+    fn inx(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.x_register = self.x_register.wrapping_add(1);
         self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn iny(&mut self, address: Address) {
+    fn iny(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.y_register = self.y_register.wrapping_add(1);
         self.set_zero_or_neg_flags(self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn isc(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            self.inc(Address::Absolute(address, false));
-            self.sbc(Address::Absolute(address, false));
-        });
+    fn isc(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::increment)?;
+        self.sbc(Address::Immediate(value))
     }
 
-    pub(crate) fn jmp(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => self.program_counter = address);
+    fn jmp(&mut self, address: Address) -> Result<(), CpuError> {
+        self.program_counter = self.operand_address(address)?;
+        Ok(())
     }
 
-    pub(crate) fn jsr(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => {
-            self.push_stack_16(self.program_counter - 1);
-            self.program_counter = address;
-        });
+    fn jsr(&mut self, address: Address) -> Result<(), CpuError> {
+        let lo = u16::from(self.read_operand(address)?);
+
+        // Internal operation while the stack pointer is read
+        self.read(STACK_PAGE + u16::from(self.stack_pointer));
+
+        // PC points to the last byte of the JSR
+        self.push_stack_16(self.program_counter);
+        let hi = u16::from(self.fetch());
+        self.program_counter = (hi << 8) | lo;
+        Ok(())
+    }
+
+    fn kil(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Implied);
+
+        // Leave PC on the offending opcode
+        self.program_counter = self.program_counter.wrapping_sub(1);
+        self.state = CpuState::Jammed;
+        Err(CpuError::Jammed(self.program_counter))
     }
 
-    pub(crate) fn las(&mut self, _address: Address) {
-        todo!("las Not Implemented")
+    fn las(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)? & self.stack_pointer;
+        self.accumulator = value;
+        self.x_register = value;
+        self.stack_pointer = value;
+        self.set_zero_or_neg_flags(value);
+        Ok(())
     }
 
-    pub(crate) fn lax(&mut self, address: Address) {
-        // Prevent doble counting cycles
-        debug_assert_matches!(address, Address::Absolute(addr, page_crossed) => {
-            self.lda(Address::Absolute(addr, false));
-            self.ldx(Address::Absolute(addr, false));
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn lax(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        self.accumulator = value;
+        self.x_register = value;
+        self.set_zero_or_neg_flags(value);
+        Ok(())
     }
 
-    pub(crate) fn lda(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            self.accumulator = self.bus.read(address);
-            self.set_zero_or_neg_flags(self.accumulator);
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn lda(&mut self, address: Address) -> Result<(), CpuError> {
+        self.accumulator = self.read_operand(address)?;
+        self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn ldx(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            self.x_register = self.bus.read(address);
-            self.set_zero_or_neg_flags(self.x_register);
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn ldx(&mut self, address: Address) -> Result<(), CpuError> {
+        self.x_register = self.read_operand(address)?;
+        self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn ldy(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            self.y_register = self.bus.read(address);
-            self.set_zero_or_neg_flags(self.y_register);
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn ldy(&mut self, address: Address) -> Result<(), CpuError> {
+        self.y_register = self.read_operand(address)?;
+        self.set_zero_or_neg_flags(self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn lsr(&mut self, address: Address) {
-        let mut inner = |value: u8| -> u8 {
-            self.status.set(StatusFlags::C, value & 1 == 1);
-            let shifted_value = value >> 1;
-            self.status.set(StatusFlags::Z, shifted_value == 0);
-            self.status.set(StatusFlags::N, false);
-            return shifted_value;
-        };
+    fn lsr(&mut self, address: Address) -> Result<(), CpuError> {
+        self.read_modify_write(address, Self::shift_right)?;
+        Ok(())
+    }
 
-        match address {
-            Address::Implied => self.accumulator = inner(self.accumulator),
-            Address::Absolute(address, _) => {
-                let value = inner(self.bus.read(address));
-                self.bus.write(address, value);
-            }
-            _ => panic!("LSR opcode with relative addressing"),
+    fn nop(&mut self, address: Address) -> Result<(), CpuError> {
+        // Unofficial NOPs with a memory operand still read it
+        if let Address::Absolute(address, _) = address {
+            self.read(address);
         }
+        Ok(())
     }
 
-    pub(crate) fn nop(&mut self, address: Address) {
-        match address {
-            Address::Absolute(_, page_crossed) => {
-                if page_crossed {
-                    self.remaining_cycles += 1;
-                }
-            }
-            _ => {
-                // Do nothing
-            }
-        }
-    }
-
-    pub(crate) fn ora(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            let value = self.bus.read(address);
-            self.accumulator |= value;
-            self.set_zero_or_neg_flags(self.accumulator);
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn ora(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        self.accumulator |= value;
+        self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn pha(&mut self, address: Address) {
+    fn pha(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.push_stack(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn php(&mut self, address: Address) {
+    fn php(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.push_stack((self.status | StatusFlags::B).bits());
+        Ok(())
     }
 
-    pub(crate) fn pla(&mut self, address: Address) {
+    fn pla(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
+        self.stack_read();
         self.accumulator = self.pop_stack();
         self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn plp(&mut self, address: Address) {
+    fn plp(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
+        self.stack_read();
+
         let old_status = self.status;
         let mut new_status = StatusFlags::from_bits_truncate(self.pop_stack());
 
@@ -526,250 +1342,339 @@ impl CPU {
         new_status.set(StatusFlags::X, old_status.contains(StatusFlags::X));
 
         self.status = new_status;
+        Ok(())
     }
 
-    pub(crate) fn rla(&mut self, address: Address) {
-        let address = match address {
-            Address::Absolute(address, _) => Address::Absolute(address, false),
-            a => a,
-        };
+    fn rla(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::rotate_left)?;
+        self.and(Address::Immediate(value))
+    }
 
-        self.rol(address);
-        self.and(address);
+    fn rol(&mut self, address: Address) -> Result<(), CpuError> {
+        self.read_modify_write(address, Self::rotate_left)?;
+        Ok(())
     }
 
-    pub(crate) fn rol(&mut self, address: Address) {
-        let mut inner = |value: u8| -> u8 {
-            // Save carry flag
-            let carry = if self.status.contains(StatusFlags::C) {
-                1
-            } else {
-                0
-            };
+    fn ror(&mut self, address: Address) -> Result<(), CpuError> {
+        self.read_modify_write(address, Self::rotate_right)?;
+        Ok(())
+    }
 
-            self.status.set(StatusFlags::C, value >> 7 == 1);
+    fn rra(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::rotate_right)?;
+        self.adc(Address::Immediate(value))
+    }
 
-            let value = value << 1 | carry;
+    fn rti(&mut self, address: Address) -> Result<(), CpuError> {
+        self.plp(address)?;
+        self.program_counter = self.pop_stack_16();
+        Ok(())
+    }
 
-            self.status.set(StatusFlags::Z, value == 0);
-            self.status
-                .set(StatusFlags::N, value & StatusFlags::N.bits() != 0);
-            value
-        };
+    fn rts(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Implied);
 
-        match address {
-            Address::Implied => self.accumulator = inner(self.accumulator),
-            Address::Absolute(address, _) => {
-                let value = inner(self.bus.read(address));
-                self.bus.write(address, value);
-            }
-            _ => panic!("ROR opcode with relative addressing"),
-        }
+        self.stack_read();
+        self.program_counter = self.pop_stack_16();
+
+        // The return address points to the last byte of the JSR
+        self.read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        Ok(())
+    }
+
+    fn sax(&mut self, address: Address) -> Result<(), CpuError> {
+        self.write(
+            self.operand_address(address)?,
+            self.accumulator & self.x_register,
+        );
+        Ok(())
     }
 
-    pub(crate) fn ror(&mut self, address: Address) {
-        let mut inner = |value: u8| -> u8 {
-            // Save carry flag
-            let carry = if self.status.contains(StatusFlags::C) {
-                1
-            } else {
-                0
-            };
+    fn sbc(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        let carry = self.status.contains(StatusFlags::C) as u16;
 
-            self.status.set(StatusFlags::C, value & 1 == 1);
+        let result = u16::from(self.accumulator) + u16::from(!value) + carry;
 
-            let value = value >> 1 | carry << 7;
+        let result_u8 = result as u8;
 
-            self.status.set(StatusFlags::Z, value == 0);
-            self.status
-                .set(StatusFlags::N, value & StatusFlags::N.bits() != 0);
-            value
-        };
+        self.status.set(StatusFlags::C, result > u16::from(u8::MAX));
+        self.status.set(StatusFlags::Z, result_u8 == 0);
+        self.status.set(
+            StatusFlags::O,
+            ((self.accumulator ^ value) & (self.accumulator ^ result_u8) & StatusFlags::N.bits())
+                > 0,
+        );
 
-        match address {
-            Address::Implied => self.accumulator = inner(self.accumulator),
-            Address::Absolute(address, _) => {
-                let value = inner(self.bus.read(address));
-                self.bus.write(address, value);
+        self.status
+            .set(StatusFlags::N, result_u8 & StatusFlags::N.bits() > 0);
+
+        // In BCD mode the flags still come from the binary difference
+        if self.decimal_mode && self.status.contains(StatusFlags::D) {
+            let borrow = 1 - carry as i16;
+            let mut lo = i16::from(self.accumulator & 0x0f) - i16::from(value & 0x0f) - borrow;
+            let mut hi = i16::from(self.accumulator >> 4) - i16::from(value >> 4) - (lo < 0) as i16;
+            if lo < 0 {
+                lo -= 0x06;
+            }
+            if hi < 0 {
+                hi -= 0x06;
             }
-            _ => panic!("ROR opcode with relative addressing"),
+            self.accumulator = ((hi << 4) | (lo & 0x0f)) as u8;
+        } else {
+            self.accumulator = result_u8;
         }
+        Ok(())
     }
 
-    pub(crate) fn rra(&mut self, address: Address) {
-        let address = match address {
-            Address::Absolute(address, _) => Address::Absolute(address, false),
-            a => a,
-        };
+    fn sec(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Implied);
 
-        self.ror(address);
-        self.adc(address);
+        self.status |= StatusFlags::C;
+        Ok(())
     }
 
-    pub(crate) fn rti(&mut self, address: Address) {
-        self.plp(address);
-        self.program_counter = self.pop_stack_16();
+    fn sed(&mut self, address: Address) -> Result<(), CpuError> {
+        debug_assert_matches!(address, Address::Implied);
+
+        self.status |= StatusFlags::D;
+        Ok(())
     }
 
-    pub(crate) fn rts(&mut self, address: Address) {
+    fn sei(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
-        self.program_counter = self.pop_stack_16() + 1;
+        self.status |= StatusFlags::I;
+        Ok(())
     }
 
-    pub(crate) fn sax(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => self.bus.write(address, self.accumulator & self.x_register));
+    fn shx(&mut self, address: Address) -> Result<(), CpuError> {
+        self.store_high(address, self.x_register)
     }
 
-    pub(crate) fn sbc(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, page_crossed) => {
-            let value = self.bus.read(address);
-            let carry = self.status.contains(StatusFlags::C) as u16;
-
-            let result = u16::from(self.accumulator) + u16::from(!value) + carry;
-
-            let result_u8 = result as u8;
-
-            self.status.set(StatusFlags::C, result > u16::from(u8::max_value()));
-            self.status.set(StatusFlags::Z, result_u8 == 0);
-            self.status.set(
-                StatusFlags::O,
-                ((self.accumulator ^ value)
-                    & (self.accumulator ^ result_u8)
-                    & StatusFlags::N.bits())
-                    > 0,
-            );
-
-            self.status.set(StatusFlags::N, result_u8 & StatusFlags::N.bits() > 0);
+    fn shy(&mut self, address: Address) -> Result<(), CpuError> {
+        self.store_high(address, self.y_register)
+    }
 
-            self.accumulator = result_u8;
-            if page_crossed {
-                self.remaining_cycles += 1;
-            }
-        });
+    fn slo(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::shift_left)?;
+        self.ora(Address::Immediate(value))
     }
 
-    pub(crate) fn sec(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Implied);
+    fn sre(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_modify_write(address, Self::shift_right)?;
+        self.eor(Address::Immediate(value))
+    }
 
-        self.status |= StatusFlags::C;
+    fn sta(&mut self, address: Address) -> Result<(), CpuError> {
+        self.write(self.operand_address(address)?, self.accumulator);
+        Ok(())
     }
 
-    pub(crate) fn sed(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Implied);
+    fn stx(&mut self, address: Address) -> Result<(), CpuError> {
+        self.write(self.operand_address(address)?, self.x_register);
+        Ok(())
+    }
 
-        self.status |= StatusFlags::D;
+    fn sty(&mut self, address: Address) -> Result<(), CpuError> {
+        self.write(self.operand_address(address)?, self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn sei(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Implied);
+    fn tas(&mut self, address: Address) -> Result<(), CpuError> {
+        self.stack_pointer = self.accumulator & self.x_register;
+        self.store_high(address, self.stack_pointer)
+    }
 
-        self.status |= StatusFlags::I;
+    // Reads the operand of an instruction, either from the instruction stream
+    // or from memory.
+    fn read_operand(&mut self, address: Address) -> Result<u8, CpuError> {
+        match address {
+            Address::Immediate(value) => Ok(value),
+            Address::Absolute(address, _) => Ok(self.read(address)),
+            _ => Err(self.invalid_addressing()),
+        }
     }
 
-    pub(crate) fn shx(&mut self, _address: Address) {
-        todo!("shx Not Implemented")
+    // Address of the memory operand of an instruction
+    fn operand_address(&self, address: Address) -> Result<u16, CpuError> {
+        match address {
+            Address::Absolute(address, _) => Ok(address),
+            _ => Err(self.invalid_addressing()),
+        }
     }
 
-    pub(crate) fn shy(&mut self, _address: Address) {
-        todo!("shy Not Implemented")
+    fn invalid_addressing(&self) -> CpuError {
+        CpuError::InvalidAddressing {
+            pc: self.instruction_address,
+        }
     }
 
-    pub(crate) fn slo(&mut self, address: Address) {
-        let address = match address {
-            Address::Absolute(address, _) => Address::Absolute(address, false),
-            a => a,
-        };
+    // Applies `modify` to the accumulator or to a memory operand and returns
+    // the result. Like the real 6502, memory operands are written back
+    // unmodified before the result is written, which is visible to memory
+    // mapped registers.
+    fn read_modify_write(
+        &mut self,
+        address: Address,
+        modify: impl FnOnce(&mut Self, u8) -> u8,
+    ) -> Result<u8, CpuError> {
+        match address {
+            Address::Implied => {
+                let value = self.accumulator;
+                self.accumulator = modify(self, value);
+                Ok(self.accumulator)
+            }
+            Address::Absolute(address, _) => {
+                let value = self.read(address);
+                self.write(address, value);
+                let value = modify(self, value);
+                self.write(address, value);
+                Ok(value)
+            }
+            _ => Err(self.invalid_addressing()),
+        }
+    }
 
-        self.asl(address);
-        self.ora(address);
+    fn shift_left(&mut self, value: u8) -> u8 {
+        self.status.set(StatusFlags::C, value >> 7 == 1);
+        let value = value << 1;
+        self.set_zero_or_neg_flags(value);
+        value
     }
 
-    pub(crate) fn sre(&mut self, address: Address) {
-        let address = match address {
-            Address::Absolute(address, _) => Address::Absolute(address, false),
-            a => a,
-        };
+    fn shift_right(&mut self, value: u8) -> u8 {
+        self.status.set(StatusFlags::C, value & 1 == 1);
+        let value = value >> 1;
+        self.set_zero_or_neg_flags(value);
+        value
+    }
 
-        self.lsr(address);
-        self.eor(address);
+    fn rotate_left(&mut self, value: u8) -> u8 {
+        let carry = self.status.contains(StatusFlags::C) as u8;
+        self.status.set(StatusFlags::C, value >> 7 == 1);
+        let value = value << 1 | carry;
+        self.set_zero_or_neg_flags(value);
+        value
     }
 
-    pub(crate) fn sta(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => self.bus.write(address, self.accumulator));
+    fn rotate_right(&mut self, value: u8) -> u8 {
+        let carry = self.status.contains(StatusFlags::C) as u8;
+        self.status.set(StatusFlags::C, value & 1 == 1);
+        let value = value >> 1 | carry << 7;
+        self.set_zero_or_neg_flags(value);
+        value
     }
 
-    pub(crate) fn stx(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => self.bus.write(address, self.x_register));
+    fn increment(&mut self, value: u8) -> u8 {
+        let value = value.wrapping_add(1);
+        self.set_zero_or_neg_flags(value);
+        value
     }
 
-    pub(crate) fn sty(&mut self, address: Address) {
-        debug_assert_matches!(address, Address::Absolute(address, _) => self.bus.write(address, self.y_register));
+    fn decrement(&mut self, value: u8) -> u8 {
+        let value = value.wrapping_sub(1);
+        self.set_zero_or_neg_flags(value);
+        value
     }
 
-    pub(crate) fn tas(&mut self, _address: Address) {
-        todo!("tas Not Implemented")
+    // Shared by the unstable SHX, SHY, AHX and TAS stores: the value written
+    // is ANDed with the high byte of the base address plus one and, when
+    // indexing crosses a page, it also replaces the high byte of the target.
+    fn store_high(&mut self, address: Address, value: u8) -> Result<(), CpuError> {
+        let (address, page_crossed) = match address {
+            Address::Absolute(address, page_crossed) => (address, page_crossed),
+            _ => return Err(self.invalid_addressing()),
+        };
+
+        let base_high = ((address >> 8) as u8).wrapping_sub(page_crossed as u8);
+        let value = value & base_high.wrapping_add(1);
+
+        let address = if page_crossed {
+            (u16::from(value) << 8) | (address & 0x00FF)
+        } else {
+            address
+        };
+
+        self.write(address, value);
+        Ok(())
     }
 
-    pub(crate) fn tax(&mut self, address: Address) {
+    fn tax(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.x_register = self.accumulator;
 
         self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn tay(&mut self, address: Address) {
+    fn tay(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.y_register = self.accumulator;
 
         self.set_zero_or_neg_flags(self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn tsx(&mut self, address: Address) {
+    fn tsx(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.x_register = self.stack_pointer;
         self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn txa(&mut self, address: Address) {
+    fn txa(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.accumulator = self.x_register;
         self.set_zero_or_neg_flags(self.x_register);
+        Ok(())
     }
 
-    pub(crate) fn txs(&mut self, address: Address) {
+    fn txs(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.stack_pointer = self.x_register;
+        Ok(())
     }
 
-    pub(crate) fn tya(&mut self, address: Address) {
+    fn tya(&mut self, address: Address) -> Result<(), CpuError> {
         debug_assert_matches!(address, Address::Implied);
 
         self.accumulator = self.y_register;
         self.set_zero_or_neg_flags(self.y_register);
+        Ok(())
     }
 
-    pub(crate) fn xaa(&mut self, _address: Address) {
-        todo!("xaa Not Implemented")
+    fn xaa(&mut self, address: Address) -> Result<(), CpuError> {
+        let value = self.read_operand(address)?;
+        self.accumulator = (self.accumulator | XAA_MAGIC) & self.x_register & value;
+        self.set_zero_or_neg_flags(self.accumulator);
+        Ok(())
     }
 }
 
 // Stack manipulation functions
-impl CPU {
+impl<B: Bus> CPU<B> {
+    // Pulling from the stack spends a cycle reading the current top of the
+    // stack before the stack pointer is incremented
+    fn stack_read(&mut self) {
+        self.read(STACK_PAGE + u16::from(self.stack_pointer));
+    }
+
     fn pop_stack(&mut self) -> u8 {
         self.stack_pointer = self.stack_pointer.wrapping_add(1);
-        self.bus.read(STACK_PAGE + u16::from(self.stack_pointer))
+        self.read(STACK_PAGE + u16::from(self.stack_pointer))
     }
 
     fn pop_stack_16(&mut self) -> u16 {
         let lo = u16::from(self.pop_stack());
         let hi = u16::from(self.pop_stack());
-        return (hi << 8) | lo;
+        (hi << 8) | lo
     }
 
     fn push_stack_16(&mut self, data: u16) {
@@ -778,87 +1683,140 @@ impl CPU {
     }
 
     fn push_stack(&mut self, data: u8) {
-        self.bus
-            .write(STACK_PAGE + u16::from(self.stack_pointer), data);
+        self.write(STACK_PAGE + u16::from(self.stack_pointer), data);
         self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
 }
 
-impl CPU {
-    fn resolve_address(&self, addressing: AddressingMode) -> Address {
-        match addressing {
-            AddressingMode::Absolute => self.absolute(0),
-            AddressingMode::AbsoluteX => self.absolute(self.x_register),
-            AddressingMode::AbsoluteY => self.absolute(self.y_register),
-            AddressingMode::Immediate => Address::Absolute(self.program_counter, false),
-            AddressingMode::Implied => Address::Implied,
+// Interrupt handling
+impl<B: Bus> CPU<B> {
+    fn interrupt(&mut self) {
+        // The next opcode is fetched, and discarded, twice
+        self.read(self.program_counter);
+        self.read(self.program_counter);
+
+        self.push_stack_16(self.program_counter);
+        let vector = self.interrupt_vector();
+        self.push_stack(((self.status - StatusFlags::B) | StatusFlags::X).bits());
+        self.status |= StatusFlags::I;
+        self.program_counter = self.read16(vector);
+
+        self.service_interrupt = false;
+    }
+
+    // The vector is picked while the status is pushed: an NMI that arrives
+    // before then hijacks a BRK or IRQ sequence
+    fn interrupt_vector(&mut self) -> u16 {
+        if self.nmi_pending {
+            self.nmi_pending = false;
+            NMI_VECTOR
+        } else {
+            IRQ_VECTOR
+        }
+    }
+}
+
+impl<B: Bus> CPU<B> {
+    fn resolve_address(&mut self, op: &OpCode) -> Address {
+        // Indexed reads only spend a cycle fixing the high byte of the
+        // address when a page is crossed, stores and read-modify-writes
+        // always do
+        let always_fix_high_byte = !op.page_cross_penalty();
+
+        match op.addressing() {
+            // JSR only fetches the high byte of its target after pushing the
+            // return address
+            AddressingMode::Absolute if op.operation() == Operation::Jsr => {
+                Address::Immediate(self.fetch())
+            }
+            AddressingMode::Absolute => Address::Absolute(self.fetch16(), false),
+            AddressingMode::AbsoluteX => self.absolute(self.x_register, always_fix_high_byte),
+            AddressingMode::AbsoluteY => self.absolute(self.y_register, always_fix_high_byte),
+            AddressingMode::Immediate => Address::Immediate(self.fetch()),
+            AddressingMode::Implied => {
+                // The byte after the opcode is read and discarded
+                self.read(self.program_counter);
+                Address::Implied
+            }
             AddressingMode::Indirect => self.indirect(),
             AddressingMode::IndirectX => self.indirect_x(),
-            AddressingMode::IndirectY => self.indirect_y(),
-            AddressingMode::Relative => self.relative(),
-            AddressingMode::ZeroPage => self.zero_page(0),
+            AddressingMode::IndirectY => self.indirect_y(always_fix_high_byte),
+            AddressingMode::Relative => Address::Relative(self.fetch()),
+            AddressingMode::ZeroPage => Address::Absolute(u16::from(self.fetch()), false),
             AddressingMode::ZeroPageX => self.zero_page(self.x_register),
             AddressingMode::ZeroPageY => self.zero_page(self.y_register),
         }
     }
 
-    fn relative(&self) -> Address {
-        let relative_address = self.bus.read(self.program_counter);
-        Address::Relative(relative_address)
+    fn zero_page(&mut self, offset: u8) -> Address {
+        let address = self.fetch();
+
+        // The base address is read while the index is added
+        self.read(u16::from(address));
+
+        Address::Absolute(u16::from(address.wrapping_add(offset)), false)
     }
 
-    fn zero_page(&self, offset: u8) -> Address {
-        let address = self.bus.read(self.program_counter).wrapping_add(offset);
-        Address::Absolute(address as u16, false)
+    fn absolute(&mut self, offset: u8, always_fix_high_byte: bool) -> Address {
+        let address = self.fetch16();
+        self.index(address, offset, always_fix_high_byte)
     }
 
-    fn absolute(&self, offset: u8) -> Address {
-        let address = self.bus.read16(self.program_counter);
+    // The index is added to the low byte of the address first, so the CPU
+    // reads from a partially computed address while it fixes the high byte.
+    fn index(&mut self, address: u16, offset: u8, always_fix_high_byte: bool) -> Address {
         let offset_address: u16 = address.wrapping_add(offset as u16);
+        let page_crossed = offset_address & 0xFF00 != address & 0xFF00;
+
+        if page_crossed || always_fix_high_byte {
+            self.read((address & 0xFF00) | (offset_address & 0x00FF));
+        }
 
-        Address::Absolute(offset_address, offset_address & 0xFF00 != address & 0xFF00)
+        Address::Absolute(offset_address, page_crossed)
     }
 
-    fn indirect(&self) -> Address {
-        let indirect_address = self.bus.read16(self.program_counter);
+    fn indirect(&mut self) -> Address {
+        let indirect_address = self.fetch16();
 
         let page = indirect_address & 0xff00;
 
-        let address_hi = u16::from(self.bus.read(page | ((indirect_address + 1) & 0xff))) << 8;
-        let address_lo = u16::from(self.bus.read(indirect_address));
+        let address_lo = u16::from(self.read(indirect_address));
+        // The high byte is read from the same page, even across a boundary
+        let address_hi =
+            u16::from(self.read(page | (indirect_address.wrapping_add(1) & 0xff))) << 8;
 
         let address = address_hi | address_lo;
 
         Address::Absolute(address, false)
     }
 
-    fn indirect_x(&self) -> Address {
-        let indirect_address = self
-            .bus
-            .read(self.program_counter)
-            .wrapping_add(self.x_register);
+    fn indirect_x(&mut self) -> Address {
+        let pointer = self.fetch();
+
+        // The pointer is read while X is added
+        self.read(u16::from(pointer));
+
+        let indirect_address = pointer.wrapping_add(self.x_register);
         let indirect_address_plus_one = indirect_address.wrapping_add(1) as u16;
 
-        let address_hi = (self.bus.read(indirect_address_plus_one) as u16) << 8;
-        let address_lo = self.bus.read(indirect_address as u16) as u16;
+        let address_lo = self.read(indirect_address as u16) as u16;
+        let address_hi = (self.read(indirect_address_plus_one) as u16) << 8;
 
         let address = address_hi | address_lo;
 
         Address::Absolute(address, false)
     }
 
-    fn indirect_y(&self) -> Address {
-        let indirect_address = self.bus.read(self.program_counter);
+    fn indirect_y(&mut self, always_fix_high_byte: bool) -> Address {
+        let indirect_address = self.fetch();
         let indirect_address_plus_one = indirect_address.wrapping_add(1) as u16;
 
-        let address_hi = (self.bus.read(indirect_address_plus_one) as u16) << 8;
-        let address_lo = self.bus.read(indirect_address as u16) as u16;
+        let address_lo = self.read(indirect_address as u16) as u16;
+        let address_hi = (self.read(indirect_address_plus_one) as u16) << 8;
 
         let address = address_hi | address_lo;
 
-        let offset_address = address.wrapping_add(u16::from(self.y_register));
-
-        Address::Absolute(offset_address, offset_address & 0xFF00 != address & 0xFF00)
+        self.index(address, self.y_register, always_fix_high_byte)
     }
 }
 
@@ -867,9 +1825,34 @@ mod tests {
 
     use std::{cell::RefCell, rc::Rc};
 
-    use crate::bus::Bus;
+    use assert_matches::assert_matches;
+
+    use crate::{
+        bus::Bus,
+        opcodes::{AddressingMode, OPCODE_TABLE},
+    };
 
-    use super::CPU;
+    use super::{
+        Access, CpuError, CpuSnapshot, CpuState, Instruction, InstructionHook, PowerUpState,
+        StatusFlags, Step, StopReason, CPU,
+    };
+
+    // Flat RAM that records every write it receives
+    struct RecordingBus {
+        ram: [u8; 65536],
+        writes: Vec<(u16, u8)>,
+    }
+
+    impl Bus for RecordingBus {
+        fn peek(&self, address: u16) -> u8 {
+            self.ram[address as usize]
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            self.writes.push((address, value));
+            self.ram[address as usize] = value;
+        }
+    }
 
     #[test]
     fn test_simple_program() {
@@ -893,37 +1876,37 @@ mod tests {
         let mut cpu = CPU::new(0x00, bus.clone());
 
         // LDA #$10
-        cpu.step();
+        cpu.step().unwrap();
 
         assert_eq!(cpu.accumulator, 0x10);
 
         // STA $20
-        cpu.step();
+        cpu.step().unwrap();
 
-        assert_eq!(bus.read(0x20), 0x10);
+        assert_eq!(bus.peek(0x20), 0x10);
 
         // LDA #$1
-        cpu.step();
+        cpu.step().unwrap();
         assert_eq!(cpu.accumulator, 0x01);
 
         // ADC $20
-        cpu.step();
+        cpu.step().unwrap();
         assert_eq!(cpu.accumulator, 0x11);
 
         // STA $21
-        cpu.step();
-        assert_eq!(bus.read(0x21), 0x11);
+        cpu.step().unwrap();
+        assert_eq!(bus.peek(0x21), 0x11);
 
         // INC $21
-        cpu.step();
-        assert_eq!(bus.read(0x21), 0x12);
+        cpu.step().unwrap();
+        assert_eq!(bus.peek(0x21), 0x12);
 
         // LDY $21
-        cpu.step();
+        cpu.step().unwrap();
         assert_eq!(cpu.y_register, 0x12);
 
         // INY
-        cpu.step();
+        cpu.step().unwrap();
         assert_eq!(cpu.y_register, 0x13);
     }
 
@@ -959,8 +1942,1232 @@ mod tests {
 
         let mut cpu = CPU::new(0x10, bus);
 
-        cpu.run_until_brk();
+        cpu.run_until_brk().unwrap();
 
         assert_eq!(10, cpu.accumulator);
     }
+
+    #[test]
+    fn test_brk() {
+        let program = [
+            0x00, 0xff, // BRK
+            0xe8, // INX
+            // .irq_handler
+            0xc8, // INY
+            0x40, // RTI
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffe] = 0x03;
+        ram[0xffff] = 0x02;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        // BRK
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0203);
+        assert_eq!(cpu.stack_pointer, 0xfa);
+        assert_eq!(cpu.total_cycles, 7);
+        assert!(cpu.status.contains(StatusFlags::I));
+        assert!(!cpu.status.contains(StatusFlags::B));
+        assert_eq!(bus.peek(0x01fd), 0x02);
+        assert_eq!(bus.peek(0x01fc), 0x02);
+        assert_eq!(bus.peek(0x01fb), 0x34);
+
+        // INY
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+
+        // RTI
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0202);
+        assert!(!cpu.status.contains(StatusFlags::B));
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+    }
+
+    #[test]
+    fn test_page_cross_penalty() {
+        let program = [
+            0xa2, 0x01, // LDX #$01
+            0xbd, 0x00, 0x03, // LDA $0300,X   -> no page cross
+            0xbd, 0xff, 0x03, // LDA $03FF,X   -> page cross, +1 cycle
+            0x9d, 0xff, 0x03, // STA $03FF,X   -> stores never pay the penalty
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 2);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 2 + 4);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 2 + 4 + 5);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 2 + 4 + 5 + 5);
+    }
+
+    #[test]
+    fn test_arr() {
+        let program = [
+            0x38, // SEC
+            0xa9, 0xff, // LDA #$FF
+            0x6b, 0xc0, // ARR #$C0     -> A = #$E0, C = 1, V = 0
+            0x6b, 0x60, // ARR #$60     -> A = #$B0, C = 0, V = 1
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus);
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0xe0);
+        assert!(cpu.status.contains(StatusFlags::C));
+        assert!(!cpu.status.contains(StatusFlags::O));
+        assert!(cpu.status.contains(StatusFlags::N));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0xb0);
+        assert!(!cpu.status.contains(StatusFlags::C));
+        assert!(cpu.status.contains(StatusFlags::O));
+    }
+
+    #[test]
+    fn test_axs_xaa_las() {
+        let program = [
+            0xa9, 0xf0, // LDA #$F0
+            0xa2, 0x3c, // LDX #$3C
+            0xcb, 0x10, // AXS #$10     -> X = ($F0 & $3C) - $10 = #$20, C = 1
+            0x8b, 0xff, // XAA #$FF     -> A = ($F0 | $EE) & $20 & $FF = #$20
+            0xbb, 0x00, 0x03, // LAS $0300,Y  -> A = X = SP = $F3 & $FD = #$F1
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0x0300] = 0xf3;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus);
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        // AXS
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 0x20);
+        assert!(cpu.status.contains(StatusFlags::C));
+
+        // XAA
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0x20);
+
+        // LAS
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0xf1);
+        assert_eq!(cpu.x_register, 0xf1);
+        assert_eq!(cpu.stack_pointer, 0xf1);
+        assert!(cpu.status.contains(StatusFlags::N));
+    }
+
+    #[test]
+    fn test_read_modify_write_dummy_write() {
+        let program = [
+            0xe6, 0x10, // INC $10
+            0x06, 0x11, // ASL $11
+            0x07, 0x12, // SLO $12
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0x10] = 0x41;
+        ram[0x11] = 0x42;
+        ram[0x12] = 0x43;
+
+        let bus = Rc::new(RefCell::new(RecordingBus {
+            ram,
+            writes: vec![],
+        }));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(
+            bus.borrow().writes,
+            vec![
+                (0x10, 0x41),
+                (0x10, 0x42),
+                (0x11, 0x42),
+                (0x11, 0x84),
+                (0x12, 0x43),
+                (0x12, 0x86),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_opcode_cycles() {
+        // Flat RAM that counts the CPU cycles it is clocked for
+        struct CountingBus {
+            ram: [u8; 65536],
+            ticks: u64,
+        }
+
+        impl Bus for CountingBus {
+            fn peek(&self, address: u16) -> u8 {
+                self.ram[address as usize]
+            }
+
+            fn write(&mut self, address: u16, value: u8) {
+                self.ram[address as usize] = value;
+            }
+
+            fn tick(&mut self) {
+                self.ticks += 1;
+            }
+        }
+
+        for (opcode, op) in OPCODE_TABLE.iter().enumerate() {
+            // Branch timing depends on the flags and is covered by nestest
+            if matches!(op.addressing(), AddressingMode::Relative) {
+                continue;
+            }
+
+            let mut ram = [0u8; 65536];
+            ram[0x0200] = opcode as u8;
+
+            let bus = Rc::new(RefCell::new(CountingBus { ram, ticks: 0 }));
+
+            let mut cpu = CPU::new(0x0200, bus.clone());
+
+            // KIL jams the CPU, but still takes its cycles
+            if let Err(error) = cpu.step() {
+                assert_eq!(error, CpuError::Jammed(0x0200), "{}", op.name());
+            }
+
+            assert_eq!(cpu.total_cycles, u64::from(op.cycles()), "{}", op.name());
+            assert_eq!(bus.borrow().ticks, cpu.total_cycles, "{}", op.name());
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut ram = [0u8; 65536];
+        ram[0x0200] = 0xe8; // INX
+        ram[0xfffc] = 0x00;
+        ram[0xfffd] = 0x02;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0000, bus);
+        cpu.stack_pointer = 0x10;
+        cpu.status -= StatusFlags::I;
+
+        cpu.reset();
+        assert_eq!(cpu.program_counter, 0x0200);
+        assert_eq!(cpu.stack_pointer, 0xfd);
+        assert!(cpu.status.contains(StatusFlags::I));
+        assert_eq!(cpu.total_cycles, 7);
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+    }
+
+    #[test]
+    fn test_reset_drops_pending_interrupts() {
+        let mut ram = [0u8; 65536];
+        ram[0x0200] = 0xe8; // INX
+        ram[0xfffc] = 0x00;
+        ram[0xfffd] = 0x02;
+        ram[0xfffa] = 0x00;
+        ram[0xfffb] = 0x03;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0000, bus);
+        cpu.status -= StatusFlags::I;
+        cpu.nmi();
+        cpu.irq_pending = true;
+        cpu.interrupt_polled = true;
+
+        cpu.reset();
+        assert!(!cpu.interrupt_polled);
+        cpu.irq_pending = false;
+
+        // The first instruction runs instead of the NMI handler
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+        assert_eq!(cpu.program_counter, 0x0201);
+    }
+
+    #[test]
+    fn test_kil() {
+        let program = [
+            0xe8, // INX
+            0x02, // KIL
+            0xe8, // INX
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffc] = 0x00;
+        ram[0xfffd] = 0x02;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus);
+
+        assert!(cpu.step().is_ok());
+        assert_eq!(cpu.step(), Err(CpuError::Jammed(0x0201)));
+        assert_eq!(cpu.state(), CpuState::Jammed);
+        assert_eq!(cpu.program_counter, 0x0201);
+
+        // Stays jammed, even when interrupted
+        cpu.nmi();
+        assert_eq!(cpu.step(), Err(CpuError::Jammed(0x0201)));
+        assert_eq!(cpu.program_counter, 0x0201);
+        assert_eq!(cpu.x_register, 1);
+
+        cpu.reset();
+        assert_eq!(cpu.state(), CpuState::Running);
+        assert!(cpu.step().is_ok());
+        assert_eq!(cpu.x_register, 2);
+    }
+
+    #[test]
+    fn test_nmi() {
+        let program = [
+            0xe8, // INX
+            0xe8, // INX
+            0xe8, // INX
+            // .nmi_handler
+            0xc8, // INY
+            0x40, // RTI
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffa] = 0x03;
+        ram[0xfffb] = 0x02;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+
+        cpu.nmi();
+
+        // INX, the NMI is polled before its last cycle
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 2);
+
+        // NMI sequence
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0203);
+        assert_eq!(cpu.stack_pointer, 0xfa);
+        assert_eq!(cpu.total_cycles, 2 + 2 + 7);
+        assert_eq!(bus.peek(0x01fd), 0x02);
+        assert_eq!(bus.peek(0x01fc), 0x02);
+        assert_eq!(bus.peek(0x01fb), 0x24);
+
+        // INY
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+
+        // RTI
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0202);
+        assert_eq!(cpu.stack_pointer, 0xfd);
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 3);
+    }
+
+    #[test]
+    fn test_irq() {
+        let program = [
+            0xe8, // INX
+            0x58, // CLI
+            0xe8, // INX
+            0xe8, // INX
+            // .irq_handler
+            0xc8, // INY
+            0x40, // RTI
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffe] = 0x04;
+        ram[0xffff] = 0x02;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.status |= StatusFlags::I;
+
+        cpu.assert_irq();
+
+        // INX, IRQ is masked
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+
+        // CLI
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0202);
+
+        // INX, CLI only takes effect after the poll
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 2);
+
+        // IRQ sequence
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0204);
+        assert!(cpu.status.contains(StatusFlags::I));
+        assert_eq!(cpu.total_cycles, 2 + 2 + 2 + 7);
+        assert_eq!(bus.peek(0x01fc), 0x03);
+        assert_eq!(bus.peek(0x01fb), 0x20);
+
+        cpu.clear_irq();
+
+        // INY
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+
+        // RTI
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0203);
+        assert!(!cpu.status.contains(StatusFlags::I));
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 3);
+    }
+
+    #[test]
+    fn test_irq_flag_delays() {
+        let program = [
+            0x78, // SEI
+            0xea, // NOP
+        ];
+        let handler = [
+            0xa9, 0x00, // LDA #$00
+            0x48, // PHA
+            0x28, // PLP
+            0xea, // NOP
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0x0300..0x0300 + handler.len()].copy_from_slice(&handler);
+        ram[0xfffe] = 0x00;
+        ram[0xffff] = 0x03;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.status -= StatusFlags::I;
+        cpu.assert_irq();
+
+        // SEI was polled before it set I, so the IRQ still goes through,
+        // with I set in the pushed flags
+        cpu.step().unwrap();
+        assert_eq!(cpu.step(), Ok(Step::Interrupt { cycles: 7 }));
+        assert_eq!(cpu.program_counter, 0x0300);
+        assert_eq!(bus.peek(0x01fb), 0x24);
+
+        // LDA, PHA, PLP
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(!cpu.status.contains(StatusFlags::I));
+
+        // The IRQ waits for one more instruction after PLP clears I
+        assert_matches!(
+            cpu.step(),
+            Ok(Step::Instruction(Instruction {
+                mnemonic: "NOP",
+                ..
+            }))
+        );
+        assert_eq!(cpu.step(), Ok(Step::Interrupt { cycles: 7 }));
+    }
+
+    #[test]
+    fn test_nmi_hijacks_brk() {
+        let program = [
+            0x00, 0x00, // BRK
+            // .nmi_handler
+            0xc8, // INY
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffa] = 0x02;
+        ram[0xfffb] = 0x02;
+        ram[0xfffe] = 0x00;
+        ram[0xffff] = 0x03;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        cpu.nmi();
+
+        // BRK goes through the NMI vector, with the B flag pushed
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0202);
+        assert_eq!(cpu.total_cycles, 7);
+        assert_eq!(bus.peek(0x01fc), 0x02);
+        assert_eq!(bus.peek(0x01fb), 0x34);
+
+        // INY, the NMI isn't serviced twice
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+        assert_eq!(cpu.program_counter, 0x0203);
+    }
+
+    #[test]
+    fn test_branch_delays_irq() {
+        // Flat RAM that raises an IRQ on the given CPU cycle
+        struct IrqBus {
+            ram: [u8; 65536],
+            ticks: u64,
+            irq_cycle: u64,
+        }
+
+        impl Bus for IrqBus {
+            fn peek(&self, address: u16) -> u8 {
+                self.ram[address as usize]
+            }
+
+            fn write(&mut self, address: u16, value: u8) {
+                self.ram[address as usize] = value;
+            }
+
+            fn tick(&mut self) {
+                self.ticks += 1;
+            }
+
+            fn irq(&self) -> bool {
+                self.ticks >= self.irq_cycle
+            }
+        }
+
+        let program = [
+            0xe8, // INX
+            0xd0, 0x00, // BNE +0
+            0xe8, // INX
+            // .irq_handler
+            0xc8, // INY
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0xfffe] = 0x04;
+        ram[0xffff] = 0x02;
+
+        // The IRQ arrives on the second cycle of the branch
+        let bus = Rc::new(RefCell::new(IrqBus {
+            ram,
+            ticks: 0,
+            irq_cycle: 4,
+        }));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.status.remove(StatusFlags::I);
+
+        // INX
+        cpu.step().unwrap();
+
+        // BNE, taken without crossing a page
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 2 + 3);
+
+        // INX runs before the IRQ
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 2);
+
+        // IRQ sequence
+        cpu.step().unwrap();
+        assert_eq!(cpu.program_counter, 0x0204);
+        assert_eq!(bus.peek(0x01fc), 0x04);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let program = [
+            0xe8, // INX
+            0xc8, // INY
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        // INX
+        cpu.step().unwrap();
+
+        let snapshot = cpu.snapshot();
+        assert_eq!(snapshot.x_register, 1);
+        assert_eq!(snapshot.program_counter, 0x0201);
+        assert_eq!(snapshot.total_cycles, 2);
+
+        // INY
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+
+        cpu.restore(&snapshot);
+        assert_eq!(cpu.snapshot(), snapshot);
+
+        // INY, again
+        cpu.step().unwrap();
+        assert_eq!(cpu.y_register, 1);
+        assert_eq!(cpu.total_cycles, 4);
+    }
+
+    #[test]
+    fn test_trace_sink() {
+        let program = [
+            0xe8, // INX
+            0xc8, // INY
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        cpu.set_trace_sink(Some(Box::new(move |line: &str| {
+            sink.borrow_mut().push(line.to_string())
+        })));
+
+        let mut expected = vec![cpu.trace()];
+
+        // INX
+        cpu.step().unwrap();
+
+        expected.push(cpu.trace());
+
+        // INY
+        cpu.step().unwrap();
+
+        assert_eq!(*lines.borrow(), expected);
+        assert!(lines.borrow()[0].starts_with("0200  E8        INX"));
+
+        cpu.set_trace_sink(None);
+        cpu.step().unwrap();
+        assert_eq!(lines.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let program = [
+            // .loop
+            0xe8, // INX
+            0xc8, // INY
+            0x4c, 0x00, 0x02, // JMP .loop
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.add_breakpoint(0x0201);
+
+        assert_eq!(cpu.step_until_break(), Ok(StopReason::Breakpoint(0x0201)));
+        assert_eq!(cpu.x_register, 1);
+        assert_eq!(cpu.y_register, 0);
+
+        // Resuming runs the instruction at the breakpoint
+        assert_eq!(cpu.step_until_break(), Ok(StopReason::Breakpoint(0x0201)));
+        assert_eq!(cpu.x_register, 2);
+        assert_eq!(cpu.y_register, 1);
+
+        cpu.remove_breakpoint(0x0201);
+        cpu.add_breakpoint(0x0202);
+
+        assert_eq!(cpu.step_until_break(), Ok(StopReason::Breakpoint(0x0202)));
+        assert_eq!(cpu.y_register, 2);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let program = [
+            // .loop
+            0xe8, // INX
+            0x86, 0x10, // STX $10
+            0x4c, 0x00, 0x02, // JMP .loop
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        let stop = cpu.run_until(|cpu, _| cpu.program_counter() == 0x0203);
+        assert_eq!(stop, Ok(StopReason::Condition));
+        assert_eq!(cpu.x_register, 1);
+
+        let stop = cpu.run_until(|_, bus| bus.peek(0x10) == 5);
+        assert_eq!(stop, Ok(StopReason::Condition));
+        assert_eq!(cpu.program_counter, 0x0203);
+
+        let target = cpu.total_cycles() + 100;
+        let stop = cpu.run_until(|cpu, _| cpu.total_cycles() >= target);
+        assert_eq!(stop, Ok(StopReason::Condition));
+        assert!(cpu.total_cycles - target < 3);
+
+        // Breakpoints still apply
+        cpu.add_breakpoint(0x0200);
+        let stop = cpu.run_until(|_, _| false);
+        assert_eq!(stop, Ok(StopReason::Breakpoint(0x0200)));
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let program = [
+            0xa5, 0x10, // LDA $10
+            0x85, 0x11, // STA $11
+            0xe6, 0x20, // INC $20
+            0x00, // BRK
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.add_watchpoint(0x11..=0x11, Access::Write);
+        cpu.add_watchpoint(0x18..=0x27, Access::Read);
+
+        assert_eq!(
+            cpu.step_until_break(),
+            Ok(StopReason::Watchpoint {
+                address: 0x11,
+                access: Access::Write,
+                pc: 0x0202
+            })
+        );
+        assert_eq!(cpu.program_counter, 0x0204);
+
+        assert_eq!(
+            cpu.step_until_break(),
+            Ok(StopReason::Watchpoint {
+                address: 0x20,
+                access: Access::Read,
+                pc: 0x0204
+            })
+        );
+        assert_eq!(cpu.program_counter, 0x0206);
+    }
+
+    #[test]
+    fn test_run_for_cycles() {
+        let program = [
+            // .loop
+            0xe8, // INX
+            0x4c, 0x00, 0x02, // JMP .loop
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        // INX, JMP
+        assert_eq!(cpu.run_for_cycles(5), Ok(0));
+        assert_eq!(cpu.total_cycles, 5);
+
+        // INX, JMP, INX
+        assert_eq!(cpu.run_for_cycles(6), Ok(1));
+        assert_eq!(cpu.total_cycles, 12);
+        assert_eq!(cpu.x_register, 3);
+    }
+
+    #[test]
+    fn test_run_instructions() {
+        let program = [
+            // .loop
+            0xe8, // INX
+            0x4c, 0x00, 0x02, // JMP .loop
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        assert_eq!(cpu.run_instructions(10), Ok(5 * 2 + 5 * 3));
+        assert_eq!(cpu.x_register, 5);
+        assert_eq!(cpu.program_counter, 0x0200);
+
+        // Same result when tracing forces the slow path
+        let lines = Rc::new(RefCell::new(0));
+        let sink_lines = lines.clone();
+        cpu.set_trace_sink(Some(Box::new(move |_: &str| *sink_lines.borrow_mut() += 1)));
+        assert_eq!(cpu.run_instructions(10), Ok(5 * 2 + 5 * 3));
+        assert_eq!(cpu.x_register, 10);
+        assert_eq!(*lines.borrow(), 10);
+    }
+
+    #[test]
+    fn test_dyn_bus() {
+        let mut ram = [0u8; 65536];
+        ram[0x0200] = 0xe8; // INX
+
+        let bus: Rc<RefCell<dyn Bus>> = Rc::new(RefCell::new(ram));
+
+        let mut cpu: CPU = CPU::new(0x0200, bus.clone());
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 1);
+        assert_eq!(bus.peek(0x0200), 0xe8);
+    }
+
+    #[test]
+    fn test_power_up_state() {
+        let mut ram = [0u8; 65536];
+        ram[0x0200] = 0xe8; // INX
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let power_up = PowerUpState {
+            x_register: 0x41,
+            stack_pointer: 0x80,
+            status: 0x25,
+            total_cycles: 100,
+            ..PowerUpState::default()
+        };
+
+        let mut cpu = CPU::with_power_up_state(0x0200, bus, power_up);
+        assert_eq!(cpu.stack_pointer, 0x80);
+        assert!(cpu.status.contains(StatusFlags::C));
+
+        // INX
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 0x42);
+        assert_eq!(cpu.total_cycles, 102);
+    }
+
+    #[test]
+    fn test_strict() {
+        let program = [
+            0xa9, 0x07, // LDA #$07
+            0xa7, 0x10, // LAX $10
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0x0010] = 0x42;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.set_strict(true);
+
+        assert!(cpu.trace().starts_with("0200  A9 07     LDA #$07"));
+        assert!(cpu.trace().ends_with("CYC:0 STRICT"));
+        cpu.step().unwrap();
+        assert!(cpu.trace().starts_with("0202  A7 10    !LAX $10 = 42"));
+        assert_eq!(
+            cpu.step(),
+            Err(CpuError::IllegalOpcode {
+                opcode: 0xa7,
+                pc: 0x0202
+            })
+        );
+        assert_eq!(cpu.program_counter, 0x0202);
+        assert_eq!(cpu.x_register, 0);
+
+        cpu.set_strict(false);
+        assert!(cpu.trace().starts_with("0202  A7 10    *LAX $10 = 42"));
+        assert!(!cpu.trace().ends_with(" STRICT"));
+        cpu.step().unwrap();
+        assert_eq!(cpu.x_register, 0x42);
+    }
+
+    #[test]
+    fn test_step() {
+        let program = [
+            0xbd, 0xff, 0x10, // LDA $10FF,X
+            0xd0, 0xfe, // BNE *
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        ram[0x1100] = 0x42;
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.x_register = 1;
+
+        assert_eq!(
+            cpu.step(),
+            Ok(Step::Instruction(Instruction {
+                address: 0x0200,
+                opcode: 0xbd,
+                mnemonic: "LDA",
+                effective_address: Some(0x1100),
+                len: 3,
+                cycles: 5,
+            }))
+        );
+
+        let step = cpu.step().unwrap();
+        assert_matches!(
+            step,
+            Step::Instruction(Instruction {
+                effective_address: Some(0x0203),
+                len: 2,
+                ..
+            })
+        );
+        assert_eq!(step.cycles(), 3);
+
+        cpu.nmi();
+        cpu.step().unwrap();
+        assert_eq!(cpu.step(), Ok(Step::Interrupt { cycles: 7 }));
+    }
+
+    #[test]
+    fn test_hooks() {
+        struct Recorder(Rc<RefCell<Vec<String>>>);
+
+        impl InstructionHook for Recorder {
+            fn before(&mut self, registers: &CpuSnapshot, opcode: u8) {
+                self.0.borrow_mut().push(format!(
+                    "before {:04X} {:02X} A:{:02X}",
+                    registers.program_counter, opcode, registers.accumulator
+                ));
+            }
+
+            fn after(&mut self, registers: &CpuSnapshot, instruction: &Instruction) {
+                self.0.borrow_mut().push(format!(
+                    "after {:04X} {} A:{:02X}",
+                    instruction.address, instruction.mnemonic, registers.accumulator
+                ));
+            }
+        }
+
+        let program = [
+            0xa9, 0x42, // LDA #$42
+            0xe8, // INX
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        let log = Rc::new(RefCell::new(vec![]));
+        cpu.add_hook(Box::new(Recorder(log.clone())));
+
+        cpu.step().unwrap();
+        assert_eq!(
+            *log.borrow(),
+            ["before 0200 A9 A:00", "after 0200 LDA A:42"]
+        );
+
+        cpu.clear_hooks();
+        cpu.step().unwrap();
+        assert_eq!(log.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_status() {
+        let program = [
+            0x38, // SEC
+            0xa9, 0x80, // LDA #$80
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.status(),
+            StatusFlags::C | StatusFlags::I | StatusFlags::X | StatusFlags::N
+        );
+        assert!(!cpu.status().contains(StatusFlags::Z));
+    }
+
+    #[test]
+    fn test_unofficial_nops() {
+        let cases: [(&[u8], Option<u16>, u64); 7] = [
+            (&[0x04, 0x10], Some(0x0010), 3),       // NOP $10
+            (&[0x0c, 0x00, 0x03], Some(0x0300), 4), // NOP $0300
+            (&[0x14, 0x10], Some(0x0011), 4),       // NOP $10,X
+            (&[0x1c, 0x00, 0x03], Some(0x0301), 4), // NOP $0300,X
+            (&[0x1c, 0xff, 0x02], Some(0x0300), 5), // NOP $02FF,X
+            (&[0x80, 0x10], None, 2),               // NOP #$10
+            (&[0x1a], None, 2),                     // NOP
+        ];
+
+        for (program, operand, cycles) in cases {
+            let mut ram = [0u8; 65536];
+            ram[0x0200..0x0200 + program.len()].copy_from_slice(program);
+
+            let bus = Rc::new(RefCell::new(ram));
+
+            let mut cpu = CPU::new(0x0200, bus.clone());
+            cpu.x_register = 1;
+            if let Some(address) = operand {
+                cpu.add_watchpoint(address..=address, Access::Read);
+            }
+
+            let step = cpu.step().unwrap();
+            assert_eq!(step.cycles(), cycles, "{:02X}", program[0]);
+            assert_eq!(cpu.program_counter, 0x0200 + program.len() as u16);
+
+            // The operand is read, like any other load
+            let read = operand.map(|address| StopReason::Watchpoint {
+                address,
+                access: Access::Read,
+                pc: 0x0200,
+            });
+            assert_eq!(cpu.watchpoint_hit, read, "{:02X}", program[0]);
+        }
+    }
+
+    #[test]
+    fn test_decimal_mode() {
+        let program = [
+            0xf8, // SED
+            0x18, // CLC
+            0xa9, 0x15, // LDA #$15
+            0x69, 0x27, // ADC #$27
+            0x69, 0x58, // ADC #$58
+            0x38, // SEC
+            0xe9, 0x01, // SBC #$01
+            0xe9, 0x01, // SBC #$01
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let bus = Rc::new(RefCell::new(ram));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.set_decimal_mode(true);
+
+        // SED, CLC, LDA
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        // ADC #$27
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0x42);
+        assert!(!cpu.status.contains(StatusFlags::C));
+
+        // ADC #$58
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0x00);
+        assert!(cpu.status.contains(StatusFlags::C));
+
+        // SEC, SBC #$01
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0x99);
+        assert!(!cpu.status.contains(StatusFlags::C));
+
+        // SBC #$01, with borrow
+        cpu.step().unwrap();
+        assert_eq!(cpu.accumulator, 0x97);
+        assert!(cpu.status.contains(StatusFlags::C));
+
+        // The 2A03 ignores the D flag
+        let mut cpu = CPU::new(0x0200, bus);
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.accumulator, 0x3c);
+    }
+
+    #[test]
+    fn test_address_wraparound() {
+        fn run(pc: u16, program: &[u8], data: &[(u16, u8)]) -> CPU<Rc<RefCell<[u8; 65536]>>> {
+            let mut ram = [0u8; 65536];
+            for (offset, &value) in program.iter().enumerate() {
+                ram[usize::from(pc.wrapping_add(offset as u16))] = value;
+            }
+            for &(address, value) in data {
+                ram[usize::from(address)] = value;
+            }
+
+            let mut cpu = CPU::new(pc, Rc::new(RefCell::new(ram)));
+            cpu.x_register = 1;
+            cpu.y_register = 1;
+            cpu.step().unwrap();
+            cpu
+        }
+
+        // LDA $FFFF,X wraps to the start of the address space
+        let cpu = run(0x0200, &[0xbd, 0xff, 0xff], &[(0x0000, 0x11)]);
+        assert_eq!(cpu.accumulator, 0x11);
+
+        // LDA $FF,X stays in page zero
+        let cpu = run(0x0200, &[0xb5, 0xff], &[(0x0000, 0x22), (0x0100, 0xee)]);
+        assert_eq!(cpu.accumulator, 0x22);
+
+        // LDA ($FE,X) reads the pointer from $FF and $00
+        let data = [
+            (0x00ff, 0x34),
+            (0x0000, 0x12),
+            (0x0100, 0x56),
+            (0x1234, 0x33),
+        ];
+        let cpu = run(0x0200, &[0xa1, 0xfe], &data);
+        assert_eq!(cpu.accumulator, 0x33);
+
+        // LDA ($FF),Y too
+        let data = [
+            (0x00ff, 0x34),
+            (0x0000, 0x12),
+            (0x0100, 0x56),
+            (0x1235, 0x44),
+        ];
+        let cpu = run(0x0200, &[0xb1, 0xff], &data);
+        assert_eq!(cpu.accumulator, 0x44);
+
+        // LDA ($FF),Y with Y pushing the address past $FFFF
+        let data = [(0x00ff, 0xff), (0x0000, 0xff)];
+        let cpu = run(0x0200, &[0xb1, 0xff], &data);
+        assert_eq!(cpu.accumulator, 0xff);
+
+        // JMP ($02FF) takes the high byte from $0200
+        let cpu = run(
+            0x0200,
+            &[0x6c, 0xff, 0x02],
+            &[(0x02ff, 0x34), (0x0300, 0x12)],
+        );
+        assert_eq!(cpu.program_counter, 0x6c34);
+
+        // JMP ($FFFF) from $FF00
+        let cpu = run(
+            0x0200,
+            &[0x6c, 0xff, 0xff],
+            &[(0xffff, 0x34), (0xff00, 0x12)],
+        );
+        assert_eq!(cpu.program_counter, 0x1234);
+
+        // Instructions can straddle the end of the address space
+        let cpu = run(0xffff, &[0xa9, 0x42], &[]);
+        assert_eq!(cpu.accumulator, 0x42);
+        assert_eq!(cpu.program_counter, 0x0001);
+        let cpu = CPU::new(0xffff, cpu.bus().clone());
+        assert!(cpu.trace().starts_with("FFFF  A9 42     LDA #$42"));
+    }
+
+    #[test]
+    fn test_oam_dma() {
+        // Flat RAM with an OAM DMA register at $4014
+        struct DmaBus {
+            ram: [u8; 65536],
+            oam_dma: Option<u8>,
+            oam: Vec<u8>,
+        }
+
+        impl Bus for DmaBus {
+            fn peek(&self, address: u16) -> u8 {
+                self.ram[address as usize]
+            }
+
+            fn write(&mut self, address: u16, value: u8) {
+                match address {
+                    0x2004 => self.oam.push(value),
+                    0x4014 => self.oam_dma = Some(value),
+                    _ => self.ram[address as usize] = value,
+                }
+            }
+
+            fn take_oam_dma(&mut self) -> Option<u8> {
+                self.oam_dma.take()
+            }
+        }
+
+        let program = [
+            0x8d, 0x14, 0x40, // STA $4014
+            0xe8, // INX
+            0x8d, 0x14, 0x40, // STA $4014
+        ];
+
+        let mut ram = [0u8; 65536];
+        ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        for i in 0..0x100 {
+            ram[0x0300 + i] = i as u8;
+        }
+
+        let bus = Rc::new(RefCell::new(DmaBus {
+            ram,
+            oam_dma: None,
+            oam: vec![],
+        }));
+
+        let mut cpu = CPU::new(0x0200, bus.clone());
+        cpu.accumulator = 0x03;
+
+        // STA $4014, written on an even cycle
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 4 + 513);
+        assert_eq!(bus.borrow().oam, (0..=0xff).collect::<Vec<u8>>());
+
+        // INX, STA $4014, written on an odd cycle so the DMA waits a cycle
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.total_cycles, 4 + 513 + 2 + 4 + 514);
+        assert_eq!(bus.borrow().oam.len(), 512);
+    }
 }
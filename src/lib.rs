@@ -1,7 +1,14 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod bus;
 pub mod cpu;
 
+pub mod apu;
+pub mod audio;
 pub mod cartridge;
 pub mod nes;
 
+mod blip;
 mod opcodes;
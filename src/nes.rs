@@ -1,47 +1,343 @@
-use crate::{bus::Bus, cartridge::Cartridge};
+use crate::{
+    apu::{self, Apu},
+    bus::Bus,
+    cartridge::{vrc6, Cartridge, ExpansionAudio},
+    cpu::{CpuError, Step, CPU},
+};
 use log::warn;
 
+pub struct Nes {
+    cpu: CPU<NesBus>,
+}
+
+impl Nes {
+    pub fn new(cartridge: Cartridge) -> Self {
+        let mut cpu = CPU::new(0x0000, NesBus::new(cartridge));
+        cpu.reset();
+
+        Self { cpu }
+    }
+
+    /// Presses the console's reset button.
+    pub fn reset(&mut self) {
+        self.cpu.bus_mut().reset();
+        self.cpu.reset();
+    }
+
+    pub fn step(&mut self) -> Result<Step, CpuError> {
+        self.cpu.step()
+    }
+
+    pub fn run_instructions(&mut self, count: u64) -> Result<u64, CpuError> {
+        self.cpu.run_instructions(count)
+    }
+
+    pub fn run_for_cycles(&mut self, cycles: u64) -> Result<u64, CpuError> {
+        self.cpu.run_for_cycles(cycles)
+    }
+
+    pub fn cpu(&self) -> &CPU<NesBus> {
+        &self.cpu
+    }
+
+    pub fn bus(&self) -> &NesBus {
+        self.cpu.bus()
+    }
+
+    pub fn bus_mut(&mut self) -> &mut NesBus {
+        self.cpu.bus_mut()
+    }
+}
+
+const DOTS_PER_SCANLINE: u16 = 341;
+const SCANLINES_PER_FRAME: u16 = 262;
+const OAM_DMA: u16 = 0x4014;
+// The PPU's I/O latch loses its value about 600ms after it was last
+// written
+const PPU_LATCH_DECAY_CYCLES: u64 = 1_073_864;
+
 pub struct NesBus {
     cpu_vram: [u8; 2048],
     cartridge: Cartridge,
+    apu: Apu,
+    // The PPU runs 3 dots for every CPU cycle
+    ppu_scanline: u16,
+    ppu_dot: u16,
+    oam_dma: Option<u8>,
+    // Last value driven on the CPU data bus, read back from locations
+    // nothing responds to
+    open_bus: u8,
+    // The PPU's own I/O latch, read back from its write-only registers
+    ppu_latch: u8,
+    ppu_latch_written: u64,
+    cycles: u64,
 }
 
 impl NesBus {
     pub fn new(cartridge: Cartridge) -> Self {
+        let mut apu = Apu::new();
+        apu.set_vrc6_enabled(matches!(
+            cartridge.expansion_audio(),
+            Some(ExpansionAudio::Vrc6 { .. })
+        ));
         Self {
             cpu_vram: [0x00; 2048],
             cartridge,
+            apu,
+            ppu_scanline: 0,
+            ppu_dot: 0,
+            oam_dma: None,
+            open_bus: 0,
+            ppu_latch: 0,
+            ppu_latch_written: 0,
+            cycles: 0,
         }
     }
+
+    /// Passes the reset line on to the APU, which goes silent.
+    pub fn reset(&mut self) {
+        self.apu.reset();
+        self.oam_dma = None;
+    }
+
+    pub fn open_bus(&self) -> u8 {
+        self.open_bus
+    }
+
+    pub fn cartridge(&self) -> &Cartridge {
+        &self.cartridge
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cartridge
+    }
+
+    pub fn apu(&self) -> &Apu {
+        &self.apu
+    }
+
+    pub fn apu_mut(&mut self) -> &mut Apu {
+        &mut self.apu
+    }
 }
 
 impl Bus for NesBus {
-    fn read(&self, address: u16) -> u8 {
+    fn tick(&mut self) {
+        self.cycles += 1;
+        if self.cycles - self.ppu_latch_written >= PPU_LATCH_DECAY_CYCLES {
+            self.ppu_latch = 0;
+        }
+
+        self.apu.clock();
+        self.cartridge.tick();
+        // TODO: The CPU is stalled while the DMC fetches its sample
+        if let Some(address) = self.apu.dmc_read_address() {
+            let value = self.peek(address);
+            self.apu.dmc_fill(value);
+        }
+
+        self.ppu_dot += 3;
+        if self.ppu_dot >= DOTS_PER_SCANLINE {
+            self.ppu_dot -= DOTS_PER_SCANLINE;
+            self.ppu_scanline = (self.ppu_scanline + 1) % SCANLINES_PER_FRAME;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.apu.irq() || self.cartridge.irq_pending()
+    }
+
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        Some((self.ppu_scanline, self.ppu_dot))
+    }
+
+    fn take_oam_dma(&mut self) -> Option<u8> {
+        self.oam_dma.take()
+    }
+
+    fn peek(&self, address: u16) -> u8 {
         match address {
             0x0000..=0x1FFF => {
                 let mirror_addr = address & 0b00000111_11111111;
                 self.cpu_vram[mirror_addr as usize]
             }
-            0x2000..=0x3FFF => 0,
-            0x6000..=0xFFFF => self.cartridge.read(address),
-            _ => {
-                warn!("Access to unmapped address: {:4X}", address);
-                0x00
-            }
+            0x2000..=0x3FFF => self.ppu_latch,
+            // The APU doesn't drive bit 5
+            apu::STATUS => self.apu.peek_status() | (self.open_bus & 0x20),
+            0x6000..=0xFFFF => self.cartridge.peek(address),
+            _ => self.open_bus,
         }
     }
 
+    fn read(&mut self, address: u16) -> u8 {
+        // $4015 is inside the CPU, so reading it doesn't reach the data bus
+        if address == apu::STATUS {
+            return self.apu.read_status() | (self.open_bus & 0x20);
+        }
+        if let 0x4000..=0x5FFF = address {
+            warn!("Access to unmapped address: {:4X}", address);
+        }
+        let value = self.peek(address);
+        self.open_bus = value;
+        value
+    }
+
     fn write(&mut self, address: u16, value: u8) {
+        self.open_bus = value;
         match address {
             0x0000..=0x1FFF => {
                 let mirror_addr = address & 0b00000111_11111111;
                 self.cpu_vram[mirror_addr as usize] = value;
             }
-            0x2000..=0x3FFF => {}
-            0x6000..=0xFFFF => self.cartridge.write(address, value),
+            0x2000..=0x3FFF => {
+                self.ppu_latch = value;
+                self.ppu_latch_written = self.cycles;
+            }
+            OAM_DMA => self.oam_dma = Some(value),
+            0x4000..=0x4013 | apu::STATUS | apu::FRAME_COUNTER => self.apu.write(address, value),
+            0x6000..=0xFFFF => {
+                let audio_address = match self.cartridge.expansion_audio() {
+                    Some(ExpansionAudio::Vrc6 {
+                        swapped_lines: true,
+                    }) => vrc6::swap_lines(address),
+                    _ => address,
+                };
+                self.apu.write_expansion(audio_address, value);
+                self.cartridge.write(address, value);
+            }
             _ => {
                 warn!("Access to unmapped address: {:4X}", address);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{apu::FRAME_COUNTER, bus::Bus, cartridge::Cartridge};
+
+    use super::{Nes, NesBus, PPU_LATCH_DECAY_CYCLES};
+
+    fn nrom_image() -> Vec<u8> {
+        let mut rom = vec![0u8; 16 + 0x4000];
+        rom[0..4].copy_from_slice(b"NES\x1a");
+        rom[4] = 1;
+        rom
+    }
+
+    fn nrom() -> Cartridge {
+        Cartridge::from_rom(&nrom_image()).unwrap()
+    }
+
+    // Counts APU interrupts in $00, acknowledging both the frame and DMC
+    // interrupts
+    fn irq_counter() -> Nes {
+        let mut rom = nrom_image();
+        let program = [
+            0x58, // CLI
+            0x4c, 0x01, 0x80, // JMP $8001
+        ];
+        let handler = [
+            0xad, 0x15, 0x40, // LDA $4015
+            0xa9, 0x0f, // LDA #$0F
+            0x8d, 0x10, 0x40, // STA $4010
+            0xe6, 0x00, // INC $00
+            0x40, // RTI
+        ];
+        rom[16..16 + program.len()].copy_from_slice(&program);
+        rom[16 + 0x10..16 + 0x10 + handler.len()].copy_from_slice(&handler);
+        // Reset and IRQ vectors
+        rom[16 + 0x3ffc..16 + 0x3ffe].copy_from_slice(&[0x00, 0x80]);
+        rom[16 + 0x3ffe..].copy_from_slice(&[0x10, 0x80]);
+        Nes::new(Cartridge::from_rom(&rom).unwrap())
+    }
+
+    #[test]
+    fn test_open_bus() {
+        let mut bus = NesBus::new(nrom());
+
+        bus.write(0x0010, 0x42);
+        assert_eq!(bus.read(0x5000), 0x42);
+
+        bus.read(0x0010);
+        bus.write(0x4014, 0x07);
+        assert_eq!(bus.read(0x4018), 0x07);
+        assert_eq!(bus.open_bus(), 0x07);
+    }
+
+    #[test]
+    fn test_ppu_latch() {
+        let mut bus = NesBus::new(nrom());
+
+        bus.write(0x2000, 0x81);
+        bus.write(0x0010, 0x42);
+        assert_eq!(bus.read(0x2000), 0x81);
+        assert_eq!(bus.read(0x3ff8), 0x81);
+    }
+
+    #[test]
+    fn test_ppu_latch_decay() {
+        let mut bus = NesBus::new(nrom());
+
+        bus.write(0x2001, 0x1e);
+        for _ in 0..PPU_LATCH_DECAY_CYCLES - 1 {
+            bus.tick();
+        }
+        assert_eq!(bus.read(0x2001), 0x1e);
+
+        bus.tick();
+        assert_eq!(bus.read(0x2001), 0x00);
+
+        // Writes refresh the latch
+        bus.write(0x2005, 0x08);
+        bus.tick();
+        assert_eq!(bus.read(0x2000), 0x08);
+    }
+
+    #[test]
+    fn test_apu_status() {
+        let mut bus = NesBus::new(nrom());
+
+        bus.write(0x4015, 0x01);
+        bus.write(0x4003, 0x08);
+        bus.write(0x0010, 0x20);
+        // Bit 5 is open bus, and the read doesn't change it
+        assert_eq!(bus.read(0x4015), 0x21);
+        bus.write(0x0010, 0x00);
+        assert_eq!(bus.read(0x4015), 0x01);
+        assert_eq!(bus.open_bus(), 0x00);
+    }
+
+    #[test]
+    fn test_reset_silences_apu() {
+        let mut nes = Nes::new(nrom());
+        nes.bus_mut().write(0x4015, 0x0f);
+        for address in [0x4003, 0x4007, 0x400B, 0x400F] {
+            nes.bus_mut().write(address, 0x08);
+        }
+        nes.bus_mut().write(0x4008, 0x7f);
+        assert_eq!(nes.bus().apu().peek_status(), 0x0f);
+
+        nes.reset();
+        assert_eq!(nes.bus().apu().peek_status(), 0x00);
+    }
+
+    #[test]
+    fn test_apu_irq() {
+        let mut nes = irq_counter();
+        nes.run_for_cycles(100_000).unwrap();
+        assert_eq!(nes.bus().peek(0x0000), 3);
+
+        // The inhibit flag keeps the frame counter quiet
+        let mut nes = irq_counter();
+        nes.bus_mut().write(FRAME_COUNTER, 0x40);
+        nes.run_for_cycles(100_000).unwrap();
+        assert_eq!(nes.bus().peek(0x0000), 0);
+
+        // The DMC interrupts when its sample ends
+        nes.bus_mut().write(0x4010, 0x8f);
+        nes.bus_mut().write(0x4015, 0x10);
+        nes.run_for_cycles(100).unwrap();
+        assert_eq!(nes.bus().peek(0x0000), 1);
+    }
+}
@@ -1,8 +1,7 @@
-use crate::cpu::CPU;
-
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Address {
     Implied,
+    Immediate(u8),
     Absolute(u16, bool), // address, is_page_cross
     Relative(u8),
 }
@@ -23,12 +22,94 @@ pub(crate) enum AddressingMode {
     Indirect,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Operation {
+    Adc,
+    Ahx,
+    Alr,
+    Anc,
+    And,
+    Arr,
+    Asl,
+    Axs,
+    Bcc,
+    Bcs,
+    Beq,
+    Bit,
+    Bmi,
+    Bne,
+    Bpl,
+    Brk,
+    Bvc,
+    Bvs,
+    Clc,
+    Cld,
+    Cli,
+    Clv,
+    Cmp,
+    Cpx,
+    Cpy,
+    Dcp,
+    Dec,
+    Dex,
+    Dey,
+    Eor,
+    Inc,
+    Inx,
+    Iny,
+    Isc,
+    Jmp,
+    Jsr,
+    Kil,
+    Las,
+    Lax,
+    Lda,
+    Ldx,
+    Ldy,
+    Lsr,
+    Nop,
+    Ora,
+    Pha,
+    Php,
+    Pla,
+    Plp,
+    Rla,
+    Rol,
+    Ror,
+    Rra,
+    Rti,
+    Rts,
+    Sax,
+    Sbc,
+    Sec,
+    Sed,
+    Sei,
+    Shx,
+    Shy,
+    Slo,
+    Sre,
+    Sta,
+    Stx,
+    Sty,
+    Tas,
+    Tax,
+    Tay,
+    Tsx,
+    Txa,
+    Txs,
+    Tya,
+    Xaa,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct OpCode {
-    execute: fn(&mut CPU, Address),
+    operation: Operation,
     name: &'static str,
     addressing: AddressingMode,
     cycles: u8,
+    // Whether crossing a page when indexing costs an extra cycle
+    page_cross_penalty: bool,
+    unofficial: bool,
 }
 
 impl OpCode {
@@ -63,8 +144,16 @@ impl OpCode {
         self.cycles
     }
 
-    pub fn execute(&self, cpu: &mut CPU, address: Address) {
-        (self.execute)(cpu, address)
+    pub fn page_cross_penalty(&self) -> bool {
+        self.page_cross_penalty
+    }
+
+    pub fn unofficial(&self) -> bool {
+        self.unofficial
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
     }
 }
 
@@ -72,1794 +161,2306 @@ impl OpCode {
 pub(crate) static OPCODE_TABLE: [OpCode; 256] = [
     // Opcode: 0x00
     OpCode {
-        execute: CPU::brk,
+        operation: Operation::Brk,
         name: "BRK",
         addressing: AddressingMode::Implied,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x01
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
-    // Opcode: 0x01
+    // Opcode: 0x02
     OpCode {
-        execute: CPU::ora,
-        name: "ORA",
-        addressing: AddressingMode::IndirectX,
-        cycles: 6,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x03
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x04
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x05
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x06
     OpCode {
-        execute: CPU::asl,
+        operation: Operation::Asl,
         name: "ASL",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x07
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x08
     OpCode {
-        execute: CPU::php,
+        operation: Operation::Php,
         name: "PHP",
         addressing: AddressingMode::Implied,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x09
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x0A
     OpCode {
-        execute: CPU::asl,
+        operation: Operation::Asl,
         name: "ASL",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x0B
     OpCode {
-        execute: CPU::anc,
+        operation: Operation::Anc,
         name: "ANC",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x0C
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x0D
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x0E
     OpCode {
-        execute: CPU::asl,
+        operation: Operation::Asl,
         name: "ASL",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x0F
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x10
     OpCode {
-        execute: CPU::bpl,
+        operation: Operation::Bpl,
         name: "BPL",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x11
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0x11
+    // Opcode: 0x12
     OpCode {
-        execute: CPU::ora,
-        name: "ORA",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x13
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x14
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x15
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x16
     OpCode {
-        execute: CPU::asl,
+        operation: Operation::Asl,
         name: "ASL",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x17
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x18
     OpCode {
-        execute: CPU::clc,
+        operation: Operation::Clc,
         name: "CLC",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x19
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x1A
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x1B
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x1C
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0x1D
     OpCode {
-        execute: CPU::ora,
+        operation: Operation::Ora,
         name: "ORA",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x1E
     OpCode {
-        execute: CPU::asl,
+        operation: Operation::Asl,
         name: "ASL",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x1F
     OpCode {
-        execute: CPU::slo,
+        operation: Operation::Slo,
         name: "SLO",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x20
     OpCode {
-        execute: CPU::jsr,
+        operation: Operation::Jsr,
         name: "JSR",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x21
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
-    // Opcode: 0x21
+    // Opcode: 0x22
     OpCode {
-        execute: CPU::and,
-        name: "AND",
-        addressing: AddressingMode::IndirectX,
-        cycles: 6,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x23
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x24
     OpCode {
-        execute: CPU::bit,
+        operation: Operation::Bit,
         name: "BIT",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x25
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x26
     OpCode {
-        execute: CPU::rol,
+        operation: Operation::Rol,
         name: "ROL",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x27
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x28
     OpCode {
-        execute: CPU::plp,
+        operation: Operation::Plp,
         name: "PLP",
         addressing: AddressingMode::Implied,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x29
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x2A
     OpCode {
-        execute: CPU::rol,
+        operation: Operation::Rol,
         name: "ROL",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x2B
     OpCode {
-        execute: CPU::anc,
+        operation: Operation::Anc,
         name: "ANC",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x2C
     OpCode {
-        execute: CPU::bit,
+        operation: Operation::Bit,
         name: "BIT",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x2D
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x2E
     OpCode {
-        execute: CPU::rol,
+        operation: Operation::Rol,
         name: "ROL",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x2F
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x30
     OpCode {
-        execute: CPU::bmi,
+        operation: Operation::Bmi,
         name: "BMI",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x31
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0x31
+    // Opcode: 0x32
     OpCode {
-        execute: CPU::and,
-        name: "AND",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x33
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x34
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x35
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x36
     OpCode {
-        execute: CPU::rol,
+        operation: Operation::Rol,
         name: "ROL",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x37
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x38
     OpCode {
-        execute: CPU::sec,
+        operation: Operation::Sec,
         name: "SEC",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x39
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x3A
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x3B
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x3C
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0x3D
     OpCode {
-        execute: CPU::and,
+        operation: Operation::And,
         name: "AND",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x3E
     OpCode {
-        execute: CPU::rol,
+        operation: Operation::Rol,
         name: "ROL",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x3F
     OpCode {
-        execute: CPU::rla,
+        operation: Operation::Rla,
         name: "RLA",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x40
     OpCode {
-        execute: CPU::rti,
+        operation: Operation::Rti,
         name: "RTI",
         addressing: AddressingMode::Implied,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x41
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
-    // Opcode: 0x41
+    // Opcode: 0x42
     OpCode {
-        execute: CPU::eor,
-        name: "EOR",
-        addressing: AddressingMode::IndirectX,
-        cycles: 6,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x43
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x44
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x45
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x46
     OpCode {
-        execute: CPU::lsr,
+        operation: Operation::Lsr,
         name: "LSR",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x47
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x48
     OpCode {
-        execute: CPU::pha,
+        operation: Operation::Pha,
         name: "PHA",
         addressing: AddressingMode::Implied,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x49
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x4A
     OpCode {
-        execute: CPU::lsr,
+        operation: Operation::Lsr,
         name: "LSR",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x4B
     OpCode {
-        execute: CPU::alr,
+        operation: Operation::Alr,
         name: "ALR",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x4C
     OpCode {
-        execute: CPU::jmp,
+        operation: Operation::Jmp,
         name: "JMP",
         addressing: AddressingMode::Absolute,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x4D
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x4E
     OpCode {
-        execute: CPU::lsr,
+        operation: Operation::Lsr,
         name: "LSR",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x4F
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x50
     OpCode {
-        execute: CPU::bvc,
+        operation: Operation::Bvc,
         name: "BVC",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x51
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0x51
+    // Opcode: 0x52
     OpCode {
-        execute: CPU::eor,
-        name: "EOR",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x53
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x54
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x55
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x56
     OpCode {
-        execute: CPU::lsr,
+        operation: Operation::Lsr,
         name: "LSR",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x57
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x58
     OpCode {
-        execute: CPU::cli,
+        operation: Operation::Cli,
         name: "CLI",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x59
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x5A
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x5B
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x5C
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0x5D
     OpCode {
-        execute: CPU::eor,
+        operation: Operation::Eor,
         name: "EOR",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x5E
     OpCode {
-        execute: CPU::lsr,
+        operation: Operation::Lsr,
         name: "LSR",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x5F
     OpCode {
-        execute: CPU::sre,
+        operation: Operation::Sre,
         name: "SRE",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x60
     OpCode {
-        execute: CPU::rts,
+        operation: Operation::Rts,
         name: "RTS",
         addressing: AddressingMode::Implied,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x61
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
-    // Opcode: 0x61
+    // Opcode: 0x62
     OpCode {
-        execute: CPU::adc,
-        name: "ADC",
-        addressing: AddressingMode::IndirectX,
-        cycles: 6,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x63
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x64
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x65
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x66
     OpCode {
-        execute: CPU::ror,
+        operation: Operation::Ror,
         name: "ROR",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x67
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x68
     OpCode {
-        execute: CPU::pla,
+        operation: Operation::Pla,
         name: "PLA",
         addressing: AddressingMode::Implied,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x69
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x6A
     OpCode {
-        execute: CPU::ror,
+        operation: Operation::Ror,
         name: "ROR",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x6B
     OpCode {
-        execute: CPU::arr,
+        operation: Operation::Arr,
         name: "ARR",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x6C
     OpCode {
-        execute: CPU::jmp,
+        operation: Operation::Jmp,
         name: "JMP",
         addressing: AddressingMode::Indirect,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x6D
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x6E
     OpCode {
-        execute: CPU::ror,
+        operation: Operation::Ror,
         name: "ROR",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x6F
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x70
     OpCode {
-        execute: CPU::bvs,
+        operation: Operation::Bvs,
         name: "BVS",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x71
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0x71
+    // Opcode: 0x72
     OpCode {
-        execute: CPU::adc,
-        name: "ADC",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x73
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x74
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x75
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x76
     OpCode {
-        execute: CPU::ror,
+        operation: Operation::Ror,
         name: "ROR",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x77
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x78
     OpCode {
-        execute: CPU::sei,
+        operation: Operation::Sei,
         name: "SEI",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x79
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x7A
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x7B
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x7C
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0x7D
     OpCode {
-        execute: CPU::adc,
+        operation: Operation::Adc,
         name: "ADC",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0x7E
     OpCode {
-        execute: CPU::ror,
+        operation: Operation::Ror,
         name: "ROR",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x7F
     OpCode {
-        execute: CPU::rra,
+        operation: Operation::Rra,
         name: "RRA",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x80
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x81
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x82
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x83
     OpCode {
-        execute: CPU::sax,
+        operation: Operation::Sax,
         name: "SAX",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x84
     OpCode {
-        execute: CPU::sty,
+        operation: Operation::Sty,
         name: "STY",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x85
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x86
     OpCode {
-        execute: CPU::stx,
+        operation: Operation::Stx,
         name: "STX",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x87
     OpCode {
-        execute: CPU::sax,
+        operation: Operation::Sax,
         name: "SAX",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x88
     OpCode {
-        execute: CPU::dey,
+        operation: Operation::Dey,
         name: "DEY",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x89
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x8A
     OpCode {
-        execute: CPU::txa,
+        operation: Operation::Txa,
         name: "TXA",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x8B
     OpCode {
-        execute: CPU::xaa,
+        operation: Operation::Xaa,
         name: "XAA",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x8C
     OpCode {
-        execute: CPU::sty,
+        operation: Operation::Sty,
         name: "STY",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x8D
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x8E
     OpCode {
-        execute: CPU::stx,
+        operation: Operation::Stx,
         name: "STX",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x8F
     OpCode {
-        execute: CPU::sax,
+        operation: Operation::Sax,
         name: "SAX",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x90
     OpCode {
-        execute: CPU::bcc,
+        operation: Operation::Bcc,
         name: "BCC",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x91
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::IndirectY,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
-    // Opcode: 0x91
+    // Opcode: 0x92
     OpCode {
-        execute: CPU::sta,
-        name: "STA",
-        addressing: AddressingMode::IndirectY,
-        cycles: 6,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x93
     OpCode {
-        execute: CPU::ahx,
+        operation: Operation::Ahx,
         name: "AHX",
         addressing: AddressingMode::IndirectY,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x94
     OpCode {
-        execute: CPU::sty,
+        operation: Operation::Sty,
         name: "STY",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x95
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x96
     OpCode {
-        execute: CPU::stx,
+        operation: Operation::Stx,
         name: "STX",
         addressing: AddressingMode::ZeroPageY,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x97
     OpCode {
-        execute: CPU::sax,
+        operation: Operation::Sax,
         name: "SAX",
         addressing: AddressingMode::ZeroPageY,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x98
     OpCode {
-        execute: CPU::tya,
+        operation: Operation::Tya,
         name: "TYA",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x99
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::AbsoluteY,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x9A
     OpCode {
-        execute: CPU::txs,
+        operation: Operation::Txs,
         name: "TXS",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x9B
     OpCode {
-        execute: CPU::tas,
+        operation: Operation::Tas,
         name: "TAS",
         addressing: AddressingMode::AbsoluteY,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x9C
     OpCode {
-        execute: CPU::shy,
+        operation: Operation::Shy,
         name: "SHY",
         addressing: AddressingMode::AbsoluteX,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x9D
     OpCode {
-        execute: CPU::sta,
+        operation: Operation::Sta,
         name: "STA",
         addressing: AddressingMode::AbsoluteX,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0x9E
     OpCode {
-        execute: CPU::shx,
+        operation: Operation::Shx,
         name: "SHX",
         addressing: AddressingMode::AbsoluteY,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0x9F
     OpCode {
-        execute: CPU::ahx,
+        operation: Operation::Ahx,
         name: "AHX",
         addressing: AddressingMode::AbsoluteY,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xA0
     OpCode {
-        execute: CPU::ldy,
+        operation: Operation::Ldy,
         name: "LDY",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA1
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA2
     OpCode {
-        execute: CPU::ldx,
+        operation: Operation::Ldx,
         name: "LDX",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA3
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xA4
     OpCode {
-        execute: CPU::ldy,
+        operation: Operation::Ldy,
         name: "LDY",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA5
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA6
     OpCode {
-        execute: CPU::ldx,
+        operation: Operation::Ldx,
         name: "LDX",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA7
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xA8
     OpCode {
-        execute: CPU::tay,
+        operation: Operation::Tay,
         name: "TAY",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xA9
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xAA
     OpCode {
-        execute: CPU::tax,
+        operation: Operation::Tax,
         name: "TAX",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xAB
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xAC
     OpCode {
-        execute: CPU::ldy,
+        operation: Operation::Ldy,
         name: "LDY",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xAD
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xAE
     OpCode {
-        execute: CPU::ldx,
+        operation: Operation::Ldx,
         name: "LDX",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xAF
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xB0
     OpCode {
-        execute: CPU::bcs,
+        operation: Operation::Bcs,
         name: "BCS",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xB1
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0xB1
+    // Opcode: 0xB2
     OpCode {
-        execute: CPU::lda,
-        name: "LDA",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xB3
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0xB4
     OpCode {
-        execute: CPU::ldy,
+        operation: Operation::Ldy,
         name: "LDY",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xB5
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xB6
     OpCode {
-        execute: CPU::ldx,
+        operation: Operation::Ldx,
         name: "LDX",
         addressing: AddressingMode::ZeroPageY,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xB7
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::ZeroPageY,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xB8
     OpCode {
-        execute: CPU::clv,
+        operation: Operation::Clv,
         name: "CLV",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xB9
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xBA
     OpCode {
-        execute: CPU::tsx,
+        operation: Operation::Tsx,
         name: "TSX",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xBB
     OpCode {
-        execute: CPU::las,
+        operation: Operation::Las,
         name: "LAS",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0xBC
     OpCode {
-        execute: CPU::ldy,
+        operation: Operation::Ldy,
         name: "LDY",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xBD
     OpCode {
-        execute: CPU::lda,
+        operation: Operation::Lda,
         name: "LDA",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xBE
     OpCode {
-        execute: CPU::ldx,
+        operation: Operation::Ldx,
         name: "LDX",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xBF
     OpCode {
-        execute: CPU::lax,
+        operation: Operation::Lax,
         name: "LAX",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0xC0
     OpCode {
-        execute: CPU::cpy,
+        operation: Operation::Cpy,
         name: "CPY",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC1
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC2
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xC3
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xC4
     OpCode {
-        execute: CPU::cpy,
+        operation: Operation::Cpy,
         name: "CPY",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC5
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC6
     OpCode {
-        execute: CPU::dec,
+        operation: Operation::Dec,
         name: "DEC",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC7
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xC8
     OpCode {
-        execute: CPU::iny,
+        operation: Operation::Iny,
         name: "INY",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xC9
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xCA
     OpCode {
-        execute: CPU::dex,
+        operation: Operation::Dex,
         name: "DEX",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xCB
     OpCode {
-        execute: CPU::axs,
+        operation: Operation::Axs,
         name: "AXS",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xCC
     OpCode {
-        execute: CPU::cpy,
+        operation: Operation::Cpy,
         name: "CPY",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xCD
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xCE
     OpCode {
-        execute: CPU::dec,
+        operation: Operation::Dec,
         name: "DEC",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xCF
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xD0
     OpCode {
-        execute: CPU::bne,
+        operation: Operation::Bne,
         name: "BNE",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xD1
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0xD1
+    // Opcode: 0xD2
     OpCode {
-        execute: CPU::cmp,
-        name: "CMP",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xD3
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xD4
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xD5
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xD6
     OpCode {
-        execute: CPU::dec,
+        operation: Operation::Dec,
         name: "DEC",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xD7
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xD8
     OpCode {
-        execute: CPU::cld,
+        operation: Operation::Cld,
         name: "CLD",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xD9
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xDA
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xDB
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xDC
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0xDD
     OpCode {
-        execute: CPU::cmp,
+        operation: Operation::Cmp,
         name: "CMP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xDE
     OpCode {
-        execute: CPU::dec,
+        operation: Operation::Dec,
         name: "DEC",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xDF
     OpCode {
-        execute: CPU::dcp,
+        operation: Operation::Dcp,
         name: "DCP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xE0
     OpCode {
-        execute: CPU::cpx,
+        operation: Operation::Cpx,
         name: "CPX",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE1
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::IndirectX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE2
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xE3
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::IndirectX,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xE4
     OpCode {
-        execute: CPU::cpx,
+        operation: Operation::Cpx,
         name: "CPX",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE5
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::ZeroPage,
         cycles: 3,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE6
     OpCode {
-        execute: CPU::inc,
+        operation: Operation::Inc,
         name: "INC",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE7
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::ZeroPage,
         cycles: 5,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xE8
     OpCode {
-        execute: CPU::inx,
+        operation: Operation::Inx,
         name: "INX",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xE9
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xEA
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xEB
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::Immediate,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xEC
     OpCode {
-        execute: CPU::cpx,
+        operation: Operation::Cpx,
         name: "CPX",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xED
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::Absolute,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xEE
     OpCode {
-        execute: CPU::inc,
+        operation: Operation::Inc,
         name: "INC",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xEF
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::Absolute,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xF0
     OpCode {
-        execute: CPU::beq,
+        operation: Operation::Beq,
         name: "BEQ",
         addressing: AddressingMode::Relative,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xF1
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::IndirectY,
         cycles: 5,
+        page_cross_penalty: true,
+        unofficial: false,
     },
-    // Opcode: 0xF1
+    // Opcode: 0xF2
     OpCode {
-        execute: CPU::sbc,
-        name: "SBC",
-        addressing: AddressingMode::IndirectY,
-        cycles: 5,
+        operation: Operation::Kil,
+        name: "KIL",
+        addressing: AddressingMode::Implied,
+        cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xF3
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::IndirectY,
         cycles: 8,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xF4
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xF5
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::ZeroPageX,
         cycles: 4,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xF6
     OpCode {
-        execute: CPU::inc,
+        operation: Operation::Inc,
         name: "INC",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xF7
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::ZeroPageX,
         cycles: 6,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xF8
     OpCode {
-        execute: CPU::sed,
+        operation: Operation::Sed,
         name: "SED",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xF9
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::AbsoluteY,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xFA
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::Implied,
         cycles: 2,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xFB
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::AbsoluteY,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
     // Opcode: 0xFC
     OpCode {
-        execute: CPU::nop,
+        operation: Operation::Nop,
         name: "NOP",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: true,
     },
     // Opcode: 0xFD
     OpCode {
-        execute: CPU::sbc,
+        operation: Operation::Sbc,
         name: "SBC",
         addressing: AddressingMode::AbsoluteX,
         cycles: 4,
+        page_cross_penalty: true,
+        unofficial: false,
     },
     // Opcode: 0xFE
     OpCode {
-        execute: CPU::inc,
+        operation: Operation::Inc,
         name: "INC",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: false,
     },
     // Opcode: 0xFF
     OpCode {
-        execute: CPU::isc,
+        operation: Operation::Isc,
         name: "ISC",
         addressing: AddressingMode::AbsoluteX,
         cycles: 7,
+        page_cross_penalty: false,
+        unofficial: true,
     },
 ];
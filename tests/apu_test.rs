@@ -0,0 +1,86 @@
+use core::str;
+use std::{fs::File, io::Read};
+
+use nessie::{bus::Bus, cartridge::Cartridge, nes::Nes};
+
+// Tests asking for a reset want it pressed after at least 100ms
+const RESET_DELAY_CYCLES: u64 = 200_000;
+
+fn run_apu_test_rom(rom: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::open(rom)?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+
+    let cartridge = Cartridge::from_rom(&buffer)?;
+    let mut nes = Nes::new(cartridge);
+
+    let mut test_is_running = false;
+    // Make sure that the test is running
+    for _ in 0..100000 {
+        nes.step()?;
+
+        if nes.bus().peek(0x6001) == 0xDE
+            && nes.bus().peek(0x6002) == 0xB0
+            && nes.bus().peek(0x6003) == 0x61
+        {
+            test_is_running = true;
+            break;
+        }
+    }
+
+    assert!(test_is_running, "Test is not running after 100,000 steps");
+
+    loop {
+        match nes.bus().peek(0x6000) {
+            0x80 => {
+                nes.step()?;
+            }
+            0x81 => {
+                nes.run_for_cycles(RESET_DELAY_CYCLES)?;
+                nes.reset();
+            }
+            _ => break,
+        }
+    }
+
+    let mut status = vec![];
+    let mut idx = 0;
+    while nes.bus().peek(0x6004 + idx) != 0 {
+        status.push(nes.bus().peek(0x6004 + idx));
+        idx += 1;
+    }
+    let status = str::from_utf8(&status)?;
+    println!("{}", status);
+
+    assert_eq!(0x00, nes.bus().peek(0x6000), "{}", status);
+    Ok(())
+}
+
+macro_rules! apu_rom_test {
+    ($func_name:ident, $file: expr) => {
+        #[test]
+        #[ignore = "needs blargg's apu_test and apu_mixer ROMs in roms/"]
+        fn $func_name() -> Result<(), Box<dyn std::error::Error>> {
+            run_apu_test_rom(&format!("roms/{}.nes", $file))
+        }
+    };
+}
+
+apu_rom_test!(test_len_ctr, "apu_test/rom_singles/1-len_ctr");
+apu_rom_test!(test_len_table, "apu_test/rom_singles/2-len_table");
+apu_rom_test!(test_irq_flag, "apu_test/rom_singles/3-irq_flag");
+apu_rom_test!(test_jitter, "apu_test/rom_singles/4-jitter");
+apu_rom_test!(test_len_timing, "apu_test/rom_singles/5-len_timing");
+apu_rom_test!(
+    test_irq_flag_timing,
+    "apu_test/rom_singles/6-irq_flag_timing"
+);
+// 7-dmc_basics and 8-dmc_rates are left out until DMC fetches stall the
+// CPU
+
+// The mixer tests play tones that cancel out, they only report once done
+apu_rom_test!(test_mixer_square, "apu_mixer/square");
+apu_rom_test!(test_mixer_triangle, "apu_mixer/triangle");
+apu_rom_test!(test_mixer_noise, "apu_mixer/noise");
+apu_rom_test!(test_mixer_dmc, "apu_mixer/dmc");
@@ -1,6 +1,11 @@
-use std::{cell::RefCell, fs::File, io::Read, rc::Rc};
+use std::{fs::File, io::Read};
 
-use nessie::{bus::Bus, cartridge::Cartridge, cpu::CPU, nes::NesBus};
+use nessie::{
+    bus::Bus,
+    cartridge::Cartridge,
+    cpu::{PowerUpState, CPU},
+    nes::NesBus,
+};
 
 #[test]
 fn test_nestest_rom() -> Result<(), Box<dyn std::error::Error>> {
@@ -9,11 +14,15 @@ fn test_nestest_rom() -> Result<(), Box<dyn std::error::Error>> {
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
 
-    let cartridge = Cartridge::from_rom(&buffer);
-    let bus = NesBus::new(cartridge);
-    let bus = Rc::new(RefCell::new(bus));
+    let cartridge = Cartridge::from_rom(&buffer)?;
+    let mut bus = NesBus::new(cartridge);
 
-    let mut cpu = CPU::new(0xC000, bus.clone());
+    // The log starts after the 7 cycles of the reset sequence
+    for _ in 0..7 {
+        bus.tick();
+    }
+
+    let mut cpu = CPU::with_power_up_state(0xC000, bus, PowerUpState::nestest());
 
     // Compare expected output to cpu trace
     let mut file = File::open("roms/nestest/nestest.expected.out")?;
@@ -25,29 +34,17 @@ fn test_nestest_rom() -> Result<(), Box<dyn std::error::Error>> {
 
         println!("{} | {}", line, trace);
 
-        // compare PC and hexdump
-        assert_eq!(&line[0..15], &trace[0..15]);
-
-        // compare asm
-        assert_eq!(&line[16..19], &trace[16..19]);
-
-        // compare registers
-        assert_eq!(&line[48..73], &trace[48..73]);
-
-        // TODO: compare CPU cycles.
-        // Disabled for now as addressing mode don't properly address page crosses
-        // For example for opcode 9D
-        assert_eq!(&line[86..], &trace[86..]);
-        cpu.step();
+        assert_eq!(line, trace);
+        cpu.step()?;
     }
 
     assert_eq!(
         0x00,
-        bus.read(0x02),
+        cpu.bus().peek(0x02),
         "nestest error code: {:4X}",
-        bus.read(0x02)
+        cpu.bus().peek(0x02)
     );
-    assert_eq!(0x00, bus.read(0x03));
+    assert_eq!(0x00, cpu.bus().peek(0x03));
 
     Ok(())
 }
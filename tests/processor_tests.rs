@@ -0,0 +1,180 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use nessie::{
+    bus::Bus,
+    cpu::{CpuError, CPU},
+};
+use serde::Deserialize;
+
+// Loader for the single-step vectors of https://github.com/SingleStepTests/ProcessorTests
+#[derive(Deserialize)]
+struct TestCase {
+    name: String,
+    initial: TestState,
+    #[serde(rename = "final")]
+    expected: TestState,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Deserialize)]
+struct TestState {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+// Flat RAM that logs every bus access
+struct TestBus {
+    ram: [u8; 65536],
+    cycles: Vec<(u16, u8, String)>,
+}
+
+impl Bus for TestBus {
+    fn peek(&self, address: u16) -> u8 {
+        self.ram[address as usize]
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.ram[address as usize];
+        self.cycles.push((address, value, "read".to_string()));
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.cycles.push((address, value, "write".to_string()));
+        self.ram[address as usize] = value;
+    }
+}
+
+fn run_test_case(test: &TestCase) {
+    let mut ram = [0u8; 65536];
+    for &(address, value) in &test.initial.ram {
+        ram[address as usize] = value;
+    }
+
+    let bus = TestBus {
+        ram,
+        cycles: vec![],
+    };
+
+    let mut cpu = CPU::new(test.initial.pc, bus);
+
+    let mut snapshot = cpu.snapshot();
+    snapshot.stack_pointer = test.initial.s;
+    snapshot.accumulator = test.initial.a;
+    snapshot.x_register = test.initial.x;
+    snapshot.y_register = test.initial.y;
+    snapshot.status = test.initial.p;
+    cpu.restore(&snapshot);
+
+    // The suite covers the KIL opcodes too, which jam the CPU
+    match cpu.step() {
+        Ok(_) | Err(CpuError::Jammed(_)) => {}
+        Err(error) => panic!("{}: {}", test.name, error),
+    }
+
+    let snapshot = cpu.snapshot();
+    assert_eq!(
+        snapshot.program_counter, test.expected.pc,
+        "{}: PC",
+        test.name
+    );
+    assert_eq!(snapshot.stack_pointer, test.expected.s, "{}: SP", test.name);
+    assert_eq!(snapshot.accumulator, test.expected.a, "{}: A", test.name);
+    assert_eq!(snapshot.x_register, test.expected.x, "{}: X", test.name);
+    assert_eq!(snapshot.y_register, test.expected.y, "{}: Y", test.name);
+    // The B and unused bits only exist on the stack
+    assert_eq!(
+        snapshot.status | 0x30,
+        test.expected.p | 0x30,
+        "{}: P",
+        test.name
+    );
+
+    let bus = cpu.bus();
+    for &(address, value) in &test.expected.ram {
+        assert_eq!(
+            bus.ram[address as usize], value,
+            "{}: RAM at {:04X}",
+            test.name, address
+        );
+    }
+
+    assert_eq!(bus.cycles, test.cycles, "{}: cycles", test.name);
+    assert_eq!(
+        snapshot.total_cycles,
+        test.cycles.len() as u64,
+        "{}: cycle count",
+        test.name
+    );
+}
+
+#[test]
+fn test_processor_tests_samples() -> Result<(), Box<dyn std::error::Error>> {
+    // Hand-written vectors in the ProcessorTests format
+    let tests: Vec<TestCase> = serde_json::from_str(
+        r#"[
+        {
+            "name": "a9 4c",
+            "initial": { "pc": 512, "s": 253, "a": 0, "x": 0, "y": 0, "p": 38,
+                         "ram": [[512, 169], [513, 76]] },
+            "final": { "pc": 514, "s": 253, "a": 76, "x": 0, "y": 0, "p": 36,
+                       "ram": [[512, 169], [513, 76]] },
+            "cycles": [[512, 169, "read"], [513, 76, "read"]]
+        },
+        {
+            "name": "20 34 12",
+            "initial": { "pc": 768, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                         "ram": [[768, 32], [769, 52], [770, 18]] },
+            "final": { "pc": 4660, "s": 251, "a": 0, "x": 0, "y": 0, "p": 36,
+                       "ram": [[508, 2], [509, 3]] },
+            "cycles": [[768, 32, "read"], [769, 52, "read"], [509, 0, "read"],
+                       [509, 3, "write"], [508, 2, "write"], [770, 18, "read"]]
+        },
+        {
+            "name": "f6 10",
+            "initial": { "pc": 1024, "s": 253, "a": 0, "x": 245, "y": 0, "p": 36,
+                         "ram": [[1024, 246], [1025, 16], [5, 127]] },
+            "final": { "pc": 1026, "s": 253, "a": 0, "x": 245, "y": 0, "p": 164,
+                       "ram": [[5, 128]] },
+            "cycles": [[1024, 246, "read"], [1025, 16, "read"], [16, 0, "read"],
+                       [5, 127, "read"], [5, 127, "write"], [5, 128, "write"]]
+        }
+    ]"#,
+    )?;
+
+    for test in &tests {
+        run_test_case(test);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs the nes6502 vectors from ProcessorTests in roms/ProcessorTests/nes6502/v1"]
+fn test_processor_tests() -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths = fs::read_dir(Path::new("roms/ProcessorTests/nes6502/v1"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    for path in paths {
+        let tests: Vec<TestCase> = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+
+        println!("{}", path.display());
+
+        for test in &tests {
+            run_test_case(test);
+        }
+    }
+
+    Ok(())
+}